@@ -0,0 +1,687 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::error::ProofError;
+use crate::value::{brak, Value};
+
+/// A square Cartan matrix of any rank from 1 up.
+///
+/// Generators are numbered from 1, the same way they are written in rule
+/// files (`E(1)`, `C(1, 2)`, ...), so `get(a, b)` takes those labels directly.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CartanMatrix {
+    rank: usize,
+    entries: Vec<i32>,
+    // The length of the longest Serre relation, `2 - min a_ij`.
+    serre_length: usize,
+}
+
+impl CartanMatrix {
+    pub fn new(rows: Vec<Vec<i32>>) -> Result<CartanMatrix, ProofError> {
+        if rows.is_empty() {
+            return Err(ProofError::Cartan("Cartan matrix is empty".to_string()));
+        }
+
+        if let Some(row) = rows.iter().find(|row| row.len() != rows.len()) {
+            return Err(ProofError::Cartan(format!(
+                "Cartan matrix must be square, got a row of length {} in a rank {} matrix",
+                row.len(),
+                rows.len()
+            )));
+        }
+
+        return Ok(CartanMatrix::square(rows));
+    }
+
+    /// `new` for rows known to be square, of any rank, zero included.
+    fn square(rows: Vec<Vec<i32>>) -> CartanMatrix {
+        let rank = rows.len();
+        let entries = rows.into_iter().flatten().collect::<Vec<i32>>();
+        let serre_length = (2 - entries.iter().min().copied().unwrap_or(0)).max(0) as usize;
+
+        return CartanMatrix { rank, entries, serre_length };
+    }
+
+    pub fn rank(&self) -> usize {
+        return self.rank;
+    }
+
+    pub fn serre_length(&self) -> usize {
+        return self.serre_length;
+    }
+
+    /// The entry `a_ij` for generator labels `i` and `j`, counted from 1, or
+    /// None if either is not a generator.
+    pub fn get(&self, i: i32, j: i32) -> Option<i32> {
+        if i < 1 || j < 1 || i as usize > self.rank || j as usize > self.rank {
+            return None;
+        }
+
+        return Some(self.at(i, j));
+    }
+
+    /// `get` with the index out of range as the error.
+    pub fn entry(&self, i: i32, j: i32) -> Result<i32, ProofError> {
+        for index in [i, j] {
+            if index < 1 || index as usize > self.rank {
+                return Err(ProofError::OutOfRange { index, rank: self.rank });
+            }
+        }
+
+        return Ok(self.at(i, j));
+    }
+
+    /// `get` for labels already known to be generators, such as the letters
+    /// of a word `Walk` has checked.
+    pub(crate) fn at(&self, i: i32, j: i32) -> i32 {
+        return self.entries[(i as usize - 1) * self.rank + (j as usize - 1)];
+    }
+
+    /// Parses rows of whitespace or comma separated integers, one row per line.
+    pub fn parse(src: &str) -> Result<CartanMatrix, ProofError> {
+        let mut rows = vec![];
+
+        for line in src.lines().map(|line| line.trim()).filter(|line| !line.is_empty()) {
+            let row = line
+                .split(|chr: char| chr.is_whitespace() || chr == ',')
+                .filter(|entry| !entry.is_empty())
+                .map(|entry| entry.parse::<i32>().map_err(|_| ProofError::Cartan(format!("Invalid Cartan matrix entry {entry}"))))
+                .collect::<Result<Vec<i32>, ProofError>>()?;
+
+            rows.push(row);
+        }
+
+        return CartanMatrix::new(rows);
+    }
+
+    pub fn load(path: &str) -> Result<CartanMatrix, ProofError> {
+        let src = std::fs::read_to_string(path).map_err(|source| ProofError::Io { path: path.to_string(), source })?;
+        return CartanMatrix::parse(&src);
+    }
+
+    /// Either a Dynkin type such as `A3` or `E8`, or a path to a matrix file.
+    /// Something shaped like a Dynkin type with no file of that name, say `A0`
+    /// or `Q3`, is reported as a bad type rather than a missing file.
+    pub fn from_arg(arg: &str) -> Result<CartanMatrix, ProofError> {
+        if let Ok(cartan) = arg.parse() {
+            return Ok(cartan);
+        }
+
+        let mut chars = arg.trim().chars();
+        let dynkin_shaped = chars.next().is_some_and(|chr| chr.is_ascii_alphabetic())
+            && !chars.as_str().trim_start_matches('_').is_empty()
+            && chars.as_str().trim_start_matches('_').chars().all(|chr| chr.is_ascii_digit());
+
+        if dynkin_shaped && !std::path::Path::new(arg).exists() {
+            return Err(ProofError::Cartan(format!("{arg} is not a Dynkin type (A1.., B2.., C2.., D4.., E6-8, F4, G2) or a matrix file")));
+        }
+
+        return CartanMatrix::load(arg);
+    }
+
+    /// Checks the generalized Cartan matrix axioms, returning every violation.
+    pub fn validate(&self) -> Vec<CartanError> {
+        let mut errors = vec![];
+
+        for i in 1..=self.rank as i32 {
+            if self.at(i, i) != 2 {
+                errors.push(CartanError::Diagonal { i, value: self.at(i, i) });
+            }
+
+            for j in 1..=self.rank as i32 {
+                if i == j {
+                    continue;
+                }
+
+                if self.at(i, j) > 0 {
+                    errors.push(CartanError::PositiveOffDiagonal { i, j, value: self.at(i, j) });
+                }
+
+                if i < j && (self.at(i, j) == 0) != (self.at(j, i) == 0) {
+                    errors.push(CartanError::ZeroMismatch { i, j });
+                }
+            }
+        }
+
+        return errors;
+    }
+
+    /// Splits the generators into the connected pieces of the Dynkin diagram.
+    pub fn components(&self) -> Vec<Vec<i32>> {
+        let mut seen = vec![false; self.rank];
+        let mut components = vec![];
+
+        for start in 1..=self.rank as i32 {
+            if seen[start as usize - 1] {
+                continue;
+            }
+
+            seen[start as usize - 1] = true;
+            let mut component = vec![start];
+            let mut next = 0;
+
+            while next < component.len() {
+                let i = component[next];
+                next += 1;
+
+                for j in 1..=self.rank as i32 {
+                    if !seen[j as usize - 1] && (self.at(i, j) != 0 || self.at(j, i) != 0) {
+                        seen[j as usize - 1] = true;
+                        component.push(j);
+                    }
+                }
+            }
+
+            component.sort();
+            components.push(component);
+        }
+
+        return components;
+    }
+
+    /// Finds positive integers `d_i` with `d_i a_ij = d_j a_ji`, the smallest
+    /// such choice on every component, or `None` if the matrix is not
+    /// symmetrizable.
+    pub fn symmetrizer(&self) -> Option<Vec<i64>> {
+        let mut d = vec![(0i64, 1i64); self.rank];
+
+        for component in self.components() {
+            d[component[0] as usize - 1] = (1, 1);
+
+            let mut queue = vec![component[0]];
+
+            while let Some(i) = queue.pop() {
+                let (num, den) = d[i as usize - 1];
+
+                for j in 1..=self.rank as i32 {
+                    let (aij, aji) = (self.at(i, j) as i64, self.at(j, i) as i64);
+
+                    if i == j || aij == 0 || aji == 0 {
+                        continue;
+                    }
+
+                    // d_j = d_i a_ij / a_ji
+                    let dj = reduce(num * aij, den * aji);
+
+                    if dj.0 <= 0 {
+                        return None;
+                    }
+
+                    match d[j as usize - 1] {
+                        (0, _) => {
+                            d[j as usize - 1] = dj;
+                            queue.push(j);
+                        }
+                        current => if current != dj {
+                            return None;
+                        }
+                    }
+                }
+            }
+
+            let lcm = component.iter().fold(1, |lcm, i| lcm / gcd(lcm, d[*i as usize - 1].1) * d[*i as usize - 1].1);
+            let scaled = component.iter().map(|i| d[*i as usize - 1].0 * (lcm / d[*i as usize - 1].1)).collect::<Vec<i64>>();
+            let common = scaled.iter().fold(0, |g, n| gcd(g, *n));
+
+            for (i, n) in component.iter().zip(scaled) {
+                d[*i as usize - 1] = (n / common, 1);
+            }
+        }
+
+        return Some(d.into_iter().map(|(n, _)| n).collect());
+    }
+
+    /// The determinant of the principal submatrix on the given generators.
+    pub fn minor(&self, labels: &[i32]) -> i128 {
+        let n = labels.len();
+
+        let mut m = labels.iter()
+            .map(|i| labels.iter().map(|j| self.at(*i, *j) as i128).collect())
+            .collect::<Vec<Vec<i128>>>();
+
+        // Bareiss elimination keeps every intermediate value an exact integer.
+        let mut sign = 1;
+        let mut prev = 1;
+
+        for k in 0..n {
+            if m[k][k] == 0 {
+                match (k + 1..n).find(|r| m[*r][k] != 0) {
+                    Some(r) => {
+                        m.swap(k, r);
+                        sign = -sign;
+                    }
+                    None => return 0,
+                }
+            }
+
+            for i in k + 1..n {
+                for j in k + 1..n {
+                    m[i][j] = (m[i][j] * m[k][k] - m[i][k] * m[k][j]) / prev;
+                }
+            }
+
+            prev = m[k][k];
+        }
+
+        return if n == 0 { 1 } else { sign * m[n - 1][n - 1] };
+    }
+
+    /// Classifies one connected component by its principal minors: finite if
+    /// they are all positive, affine if only the full determinant vanishes,
+    /// and indefinite otherwise (hyperbolic when every proper connected
+    /// subdiagram is finite or affine).
+    ///
+    /// Finite and affine components are symmetrizable, and there the leading
+    /// minors have the signs of those of a symmetric matrix, so Sylvester's
+    /// criterion saves going through all `2^n` principal minors.
+    pub fn classify_component(&self, labels: &[i32]) -> Classification {
+        let n = labels.len();
+
+        let proper_positive = self.restrict(labels).symmetrizer().is_some() && (1..n).all(|k| self.minor(&labels[..k]) > 0);
+
+        let det = self.minor(labels);
+
+        if proper_positive && det > 0 {
+            return Classification::Finite;
+        }
+
+        if proper_positive && det == 0 {
+            return Classification::Affine;
+        }
+
+        let hyperbolic = labels.iter().all(|removed| {
+            let rest = labels.iter().copied().filter(|i| i != removed).collect::<Vec<i32>>();
+
+            self.restrict(&rest)
+                .components()
+                .iter()
+                .map(|component| component.iter().map(|i| rest[*i as usize - 1]).collect::<Vec<i32>>())
+                .all(|component| matches!(self.classify_component(&component), Classification::Finite | Classification::Affine))
+        });
+
+        return if hyperbolic { Classification::Hyperbolic } else { Classification::Indefinite };
+    }
+
+    /// Classifies every connected component of the Dynkin diagram.
+    pub fn classify(&self) -> Vec<(Vec<i32>, Classification)> {
+        return self.components()
+            .into_iter()
+            .map(|component| {
+                let class = self.classify_component(&component);
+                (component, class)
+            })
+            .collect();
+    }
+
+    /// A human readable summary of the checks above.
+    pub fn report(&self) -> String {
+        let mut report = format!("Cartan matrix of rank {}:\n{self}", self.rank);
+
+        let errors = self.validate();
+
+        if !errors.is_empty() {
+            report += "not a generalized Cartan matrix:\n";
+
+            for error in errors {
+                report += &format!("  {error}\n");
+            }
+
+            return report;
+        }
+
+        match self.symmetrizer() {
+            Some(d) => {
+                let d = d.iter().map(|d| d.to_string()).collect::<Vec<String>>();
+                report += &format!("symmetrizable with D = diag({})\n", d.join(", "));
+            }
+            None => report += "not symmetrizable\n",
+        }
+
+        for (component, class) in self.classify() {
+            let labels = component.iter().map(|i| i.to_string()).collect::<Vec<String>>();
+            report += &format!("generators {{{}}}: {class}\n", labels.join(", "));
+        }
+
+        return report;
+    }
+
+    /// The Serre relations `ad(X_i)^(1 - a_ij) X_j = 0` for `i != j`, where `X`
+    /// is the generator `kind` (`E` or `F`). Each relation is written out in
+    /// every bracketing: a bracket with one `X_j` and no `[X_i, X_i]` is
+    /// `X_j` bracketed with `X_i` once per level, on either side, and equal
+    /// to the relation up to sign.
+    pub fn serre_relations(&self, kind: &str) -> Vec<Value> {
+        let mut relations = vec![];
+
+        for i in 1..=self.rank as i32 {
+            for j in 1..=self.rank as i32 {
+                if i == j {
+                    continue;
+                }
+
+                let x = Value::Kind(kind.to_string(), i);
+                let mut shapes = vec![Value::Kind(kind.to_string(), j)];
+
+                for _ in 0..1 - self.at(i, j) {
+                    shapes = shapes
+                        .into_iter()
+                        .flat_map(|shape| [brak(shape.clone(), x.clone()), brak(x.clone(), shape)])
+                        .collect();
+                }
+
+                for relation in shapes {
+                    if !relations.contains(&relation) {
+                        relations.push(relation);
+                    }
+                }
+            }
+        }
+
+        return relations;
+    }
+
+    /// Whether the left-normed bracket `[..[[X_w0, X_w1], X_w2].., X_wn]` is one
+    /// of the Serre relations above.
+    pub fn is_serre_word(&self, word: &[i32]) -> bool {
+        if word.len() < 2 || word.len() > self.serre_length || word[0] == word[1] {
+            return false;
+        }
+
+        for (i, j) in [(word[1], word[0]), (word[0], word[1])] {
+            let k = 1 - self.at(i, j);
+
+            if word.len() as i32 == k + 1 && word[2..].iter().all(|w| *w == i) {
+                return true;
+            }
+        }
+
+        return false;
+    }
+
+    fn restrict(&self, labels: &[i32]) -> CartanMatrix {
+        return CartanMatrix::square(labels.iter()
+            .map(|i| labels.iter().map(|j| self.at(*i, *j)).collect())
+            .collect());
+    }
+
+    /// The Cartan matrix of a finite type Dynkin diagram, following the
+    /// Bourbaki numbering of the nodes and `a_ij = 2 (a_i, a_j) / (a_i, a_i)`.
+    pub fn dynkin(kind: char, n: usize) -> Option<CartanMatrix> {
+        let valid = match kind {
+            'A' => n >= 1,
+            'B' => n >= 2,
+            'C' => n >= 2,
+            'D' => n >= 4,
+            'E' => (6..=8).contains(&n),
+            'F' => n == 4,
+            'G' => n == 2,
+            _ => false,
+        };
+
+        if !valid {
+            return None;
+        }
+
+        let mut rows = (0..n)
+            .map(|i| (0..n).map(|j| if i == j { 2 } else { 0 }).collect())
+            .collect::<Vec<Vec<i32>>>();
+
+        let mut link = |i: usize, j: usize| {
+            rows[i][j] = -1;
+            rows[j][i] = -1;
+        };
+
+        match kind {
+            'E' => {
+                // 1 - 3 - 4 - 5 - ... with 2 hanging off 4.
+                link(0, 2);
+                link(1, 3);
+                for i in 2..n - 1 {
+                    link(i, i + 1);
+                }
+            }
+            'D' => {
+                for i in 0..n - 2 {
+                    link(i, i + 1);
+                }
+                link(n - 3, n - 1);
+            }
+            _ => {
+                for i in 0..n - 1 {
+                    link(i, i + 1);
+                }
+            }
+        }
+
+        match kind {
+            'B' => rows[n - 1][n - 2] = -2,
+            'C' => rows[n - 2][n - 1] = -2,
+            'F' => rows[2][1] = -2,
+            'G' => rows[0][1] = -3,
+            _ => {}
+        }
+
+        return CartanMatrix::new(rows).ok();
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Classification {
+    Finite,
+    Affine,
+    Hyperbolic,
+    Indefinite,
+}
+
+impl Display for Classification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Classification::Finite => write!(f, "finite"),
+            Classification::Affine => write!(f, "affine"),
+            Classification::Hyperbolic => write!(f, "indefinite (hyperbolic)"),
+            Classification::Indefinite => write!(f, "indefinite"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CartanError {
+    Diagonal { i: i32, value: i32 },
+    PositiveOffDiagonal { i: i32, j: i32, value: i32 },
+    ZeroMismatch { i: i32, j: i32 },
+}
+
+impl Display for CartanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CartanError::Diagonal { i, value } => write!(f, "C({i}, {i}) = {value}, expected 2"),
+            CartanError::PositiveOffDiagonal { i, j, value } => write!(f, "C({i}, {j}) = {value} is positive"),
+            CartanError::ZeroMismatch { i, j } => write!(f, "C({i}, {j}) and C({j}, {i}) must be zero together"),
+        }
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    return if b == 0 { a.abs() } else { gcd(b, a % b) };
+}
+
+fn reduce(num: i64, den: i64) -> (i64, i64) {
+    let g = gcd(num, den) * den.signum();
+    return (num / g, den / g);
+}
+
+impl FromStr for CartanMatrix {
+    type Err = ();
+
+    /// Parses a Dynkin type like `A3`, `B_4` or `G2`.
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let mut chars = src.trim().chars();
+        let kind = chars.next().ok_or(())?.to_ascii_uppercase();
+        let rank = chars.as_str().trim_start_matches('_');
+
+        let n = rank.parse::<usize>().map_err(|_| ())?;
+
+        return CartanMatrix::dynkin(kind, n).ok_or(());
+    }
+}
+
+impl Display for CartanMatrix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.entries.chunks(self.rank.max(1)) {
+            let row = row.iter().map(|entry| format!("{entry:>3}")).collect::<Vec<String>>();
+            writeln!(f, "{}", row.join(" "))?;
+        }
+
+        return Ok(());
+    }
+}
+
+///////
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::value::e;
+    use crate::RuleSet;
+
+    fn class(src: &str) -> Vec<Classification> {
+        let cartan = CartanMatrix::parse(src).unwrap_or_else(|err| panic!("{err}"));
+        return cartan.classify().into_iter().map(|(_, class)| class).collect();
+    }
+
+    /// The cycle of `n` nodes, affine `A(n - 1)`.
+    fn cycle(n: usize) -> CartanMatrix {
+        let rows = (0..n)
+            .map(|i| (0..n).map(|j| if i == j { 2 } else if (i + 1) % n == j || (j + 1) % n == i { -1 } else { 0 }).collect())
+            .collect();
+        return CartanMatrix::new(rows).unwrap();
+    }
+
+    #[test]
+    fn a_n_is_finite() {
+        for n in [1, 2, 3, 8, 70] {
+            let cartan = CartanMatrix::dynkin('A', n).unwrap();
+            assert_eq!(cartan.classify(), [((1..=n as i32).collect(), Classification::Finite)]);
+        }
+
+        assert_eq!(class("2 -1\n-3 2"), [Classification::Finite]);
+    }
+
+    #[test]
+    fn affine_matrices_have_a_zero_determinant() {
+        assert_eq!(class("2 -2\n-2 2"), [Classification::Affine]);
+        assert_eq!(class("2 -1\n-4 2"), [Classification::Affine]);
+        assert_eq!(cycle(3).classify()[0].1, Classification::Affine);
+        assert_eq!(cycle(70).classify()[0].1, Classification::Affine);
+    }
+
+    #[test]
+    fn hyperbolic_when_every_proper_subdiagram_is_finite_or_affine() {
+        assert_eq!(class("2 -3\n-3 2"), [Classification::Hyperbolic]);
+        assert_eq!(class("2 -1 -1\n-1 2 -2\n-1 -1 2"), [Classification::Hyperbolic]);
+        assert_eq!(class("2 -3 0 0\n-3 2 -1 0\n0 -1 2 -3\n0 0 -3 2"), [Classification::Indefinite]);
+    }
+
+    #[test]
+    fn components_are_classified_apart() {
+        assert_eq!(class("2 0 0\n0 2 -2\n0 -2 2"), [Classification::Finite, Classification::Affine]);
+    }
+
+    #[test]
+    fn dynkin_types_follow_bourbaki() {
+        let parse = |src| CartanMatrix::parse(src).unwrap();
+
+        assert_eq!(CartanMatrix::dynkin('B', 3).unwrap(), parse("2 -1 0\n-1 2 -1\n0 -2 2"));
+        assert_eq!(CartanMatrix::dynkin('C', 3).unwrap(), parse("2 -1 0\n-1 2 -2\n0 -1 2"));
+        assert_eq!(CartanMatrix::dynkin('D', 4).unwrap(), parse("2 -1 0 0\n-1 2 -1 -1\n0 -1 2 0\n0 -1 0 2"));
+        assert_eq!(CartanMatrix::dynkin('F', 4).unwrap(), parse("2 -1 0 0\n-1 2 -1 0\n0 -2 2 -1\n0 0 -1 2"));
+        assert_eq!(CartanMatrix::dynkin('G', 2).unwrap(), parse("2 -3\n-1 2"));
+        assert_eq!(
+            CartanMatrix::dynkin('E', 6).unwrap(),
+            parse("2 0 -1 0 0 0\n0 2 0 -1 0 0\n-1 0 2 -1 0 0\n0 -1 -1 2 -1 0\n0 0 0 -1 2 -1\n0 0 0 0 -1 2")
+        );
+
+        // Each type has its textbook determinant and is finite.
+        let determinants = [('B', 5, 2), ('C', 5, 2), ('D', 6, 4), ('E', 6, 3), ('E', 7, 2), ('E', 8, 1), ('F', 4, 1), ('G', 2, 1)];
+
+        for (kind, n, determinant) in determinants {
+            let cartan = CartanMatrix::dynkin(kind, n).unwrap();
+            let labels = (1..=n as i32).collect::<Vec<i32>>();
+
+            assert_eq!(cartan.minor(&labels), determinant, "{kind}{n}");
+            assert_eq!(cartan.classify(), [(labels, Classification::Finite)], "{kind}{n}");
+        }
+
+        for (kind, n) in [('A', 0), ('B', 1), ('C', 1), ('D', 3), ('E', 5), ('E', 9), ('F', 3), ('G', 3), ('Q', 3)] {
+            assert_eq!(CartanMatrix::dynkin(kind, n), None, "{kind}{n}");
+        }
+    }
+
+    #[test]
+    fn bad_matrices_and_arguments_are_reported() {
+        let reason = |result: Result<CartanMatrix, ProofError>| match result {
+            Err(ProofError::Cartan(reason)) => reason,
+            other => panic!("expected a Cartan error, got {other:?}"),
+        };
+
+        assert_eq!(reason(CartanMatrix::parse("2 x\n-1 2")), "Invalid Cartan matrix entry x");
+        assert_eq!(reason(CartanMatrix::parse(" \n\n")), "Cartan matrix is empty");
+        assert!(reason(CartanMatrix::parse("2 -1 0\n-1 2")).contains("must be square"));
+        assert!(matches!(CartanMatrix::load("./no/such/matrix"), Err(ProofError::Io { .. })));
+
+        assert_eq!(CartanMatrix::from_arg("b_3").unwrap(), CartanMatrix::dynkin('B', 3).unwrap());
+        assert_eq!(CartanMatrix::from_arg("./src/matrix").unwrap(), CartanMatrix::load("./src/matrix").unwrap());
+        assert!(reason(CartanMatrix::from_arg("A0")).contains("not a Dynkin type"));
+        assert!(reason(CartanMatrix::from_arg("Q3")).contains("not a Dynkin type"));
+        assert!(matches!(CartanMatrix::from_arg("./no/such/matrix"), Err(ProofError::Io { .. })));
+    }
+
+    /// `ad(X_i)^n X_j`, bracketing on the left.
+    fn ad(kind: &str, i: i32, n: i32, j: i32) -> Value {
+        return (0..n).fold(Value::Kind(kind.to_string(), j), |x, _| brak(Value::Kind(kind.to_string(), i), x));
+    }
+
+    #[test]
+    fn serre_relations_reduce_to_zero_in_a2_and_b2() {
+        for (cartan, k12, k21) in [(CartanMatrix::dynkin('A', 2).unwrap(), 2, 2), (CartanMatrix::dynkin('B', 2).unwrap(), 2, 3)] {
+            let cartan = Arc::new(cartan);
+            let rules = RuleSet::serre(&cartan);
+            let simplified = |value| crate::simplify(value, &rules, &cartan).unwrap();
+
+            assert_eq!(1 - cartan.at(1, 2), k12);
+
+            for kind in ["E", "F"] {
+                assert_eq!(simplified(ad(kind, 1, k12, 2)), Value::zero());
+                assert_eq!(simplified(ad(kind, 2, k21, 1)), Value::zero());
+                assert_ne!(simplified(ad(kind, 1, k12 - 1, 2)), Value::zero());
+            }
+        }
+    }
+
+    #[test]
+    fn serre_words_are_the_left_normed_relations() {
+        let matrices = [
+            CartanMatrix::dynkin('A', 2).unwrap(),
+            CartanMatrix::dynkin('B', 2).unwrap(),
+            CartanMatrix::dynkin('G', 2).unwrap(),
+            CartanMatrix::load("./src/matrix").unwrap(),
+        ];
+
+        for cartan in matrices {
+            let relations = cartan.serre_relations("E");
+            let rank = cartan.rank() as i32;
+
+            // Every word up to the longest relation, in base `rank`.
+            for length in 2..=cartan.serre_length() as u32 {
+                for n in 0..rank.pow(length) {
+                    let word = (0..length).map(|k| n / rank.pow(k) % rank + 1).collect::<Vec<i32>>();
+                    let bracket = word[1..].iter().fold(e(word[0]), |x, i| brak(x, e(*i)));
+
+                    assert_eq!(cartan.is_serre_word(&word), relations.contains(&bracket), "{word:?} in\n{cartan}");
+                }
+            }
+        }
+    }
+}
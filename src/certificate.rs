@@ -0,0 +1,421 @@
+use std::collections::HashMap;
+
+use crate::cartan::CartanMatrix;
+use crate::error::ProofError;
+use crate::integer::Integer;
+use crate::parser::{self, Guard, Rule, AST};
+use crate::ring::Coefficients;
+use crate::trace::{replace_at, show_position, Action, Step, Trace};
+use crate::value::*;
+use crate::{RuleSet, Verdict};
+
+pub(crate) type Substitution = HashMap<String, Value>;
+
+pub(crate) fn instantiate(ast: &AST, substitution: &Substitution, cartan: &CartanMatrix) -> Result<Value, String> {
+    let index = |ast: &AST| -> Result<i32, String> {
+        let value = instantiate(ast, substitution, cartan)?;
+
+        return match value.into_number().and_then(|n| n.to_i32()) {
+            Some(i) if i >= 1 && i as usize <= cartan.rank() => Ok(i),
+            _ => Err(format!("{value} is not a generator index")),
+        };
+    };
+
+    let build = |ast: &AST| instantiate(ast, substitution, cartan).map(Box::new);
+
+    match ast {
+        AST::Named(name) => return substitution.get(name).cloned().ok_or(format!("{name} is not bound")),
+        AST::Value(value) => return Ok(value.clone()),
+        AST::Kind(name, var) => return Ok(Value::Kind(name.clone(), index(&AST::Named(var.clone()))?)),
+        AST::Negative(a) => return Ok(Value::Negative(build(a)?)),
+        AST::Braket(a, b) => return Ok(Value::Braket(build(a)?, build(b)?)),
+        AST::Add(a, b) => return Ok(Value::Add(build(a)?, build(b)?)),
+        AST::Mul(a, b) => return Ok(Value::Mul(build(a)?, build(b)?)),
+        AST::C(a, b) => return Ok(number(Integer::from(cartan.entry(index(a)?, index(b)?).map_err(|err| err.to_string())?))),
+    }
+}
+
+fn sum_terms(value: Value, out: &mut Vec<Value>) {
+    match value {
+        Value::Add(a, b) => {
+            sum_terms(*a, out);
+            sum_terms(*b, out);
+        }
+        value => out.push(value),
+    }
+}
+
+fn sum(mut terms: Vec<Value>) -> Value {
+    let last = terms.pop().unwrap_or_else(Value::zero);
+    return terms.into_iter().rev().fold(last, |acc, term| Value::Add(Box::new(term), Box::new(acc)));
+}
+
+/// The value up to what matching ignores: the order and grouping of sums
+/// and which side of a product its number is on. A negated number is a
+/// number, the way a certificate reads it back.
+fn canonical(value: &Value) -> Value {
+    match value {
+        Value::Number(_) | Value::Kind(_, _) => return value.clone(),
+        Value::Negative(a) => {
+            return match canonical(a) {
+                Value::Number(n) => Value::Number(-n),
+                a => Value::Negative(Box::new(a)),
+            };
+        }
+        Value::Mul(a, b) => {
+            let (a, b) = (canonical(a), canonical(b));
+
+            if b.into_number().is_some() && a.into_number().is_none() {
+                return Value::Mul(Box::new(b), Box::new(a));
+            }
+
+            return Value::Mul(Box::new(a), Box::new(b));
+        }
+        Value::Braket(a, b) => return Value::Braket(Box::new(canonical(a)), Box::new(canonical(b))),
+        Value::Add(_, _) => {
+            let mut terms = vec![];
+            sum_terms(value.clone(), &mut terms);
+
+            let mut terms = terms.iter().map(canonical).collect::<Vec<Value>>();
+            terms.sort_by_key(|term| term.to_string());
+
+            return sum(terms);
+        }
+    }
+}
+
+fn subterm<'a>(value: &'a Value, position: &[usize]) -> Option<&'a Value> {
+    let Some((i, rest)) = position.split_first() else {
+        return Some(value);
+    };
+
+    match (value, i) {
+        (Value::Braket(a, _) | Value::Add(a, _) | Value::Mul(a, _) | Value::Negative(a), 0) => return subterm(a, rest),
+        (Value::Braket(_, b) | Value::Add(_, b) | Value::Mul(_, b), 1) => return subterm(b, rest),
+        _ => return None,
+    }
+}
+
+///////
+
+/// Whether `after` is what `rule` rewrites `before` to under `substitution`,
+/// as written or, for a bracket pattern, flipped with its result negated.
+/// Terms of a sum the pattern does not cover are carried along.
+fn rule_step(rule: &Rule, step: &Step, cartan: &CartanMatrix) -> Result<(), String> {
+    let substitution = step.substitution.iter().cloned().collect::<Substitution>();
+
+    for guard in &rule.guards {
+        let (a, b, equal) = match guard {
+            Guard::Equal(a, b) => (a, b, true),
+            Guard::NotEqual(a, b) => (a, b, false),
+        };
+
+        let (a, b) = (instantiate(a, &substitution, cartan)?, instantiate(b, &substitution, cartan)?);
+
+        if (canonical(&a) == canonical(&b)) != equal {
+            return Err(format!("the guard {guard} does not hold"));
+        }
+    }
+
+    let mut variants = vec![(rule.pattern.clone(), rule.expr.clone())];
+
+    if let Some(flipped) = rule.pattern.flip() {
+        variants.push((flipped, rule.expr.negate()));
+    }
+
+    let mut before = vec![];
+    sum_terms(canonical(&step.before), &mut before);
+
+    for (pattern, expr) in variants {
+        let mut pattern_terms = vec![];
+        sum_terms(canonical(&instantiate(&pattern, &substitution, cartan)?), &mut pattern_terms);
+
+        let mut leftover = before.clone();
+
+        let covered = pattern_terms.iter().all(|term| match leftover.iter().position(|t| t == term) {
+            Some(i) => {
+                leftover.remove(i);
+                true
+            }
+            None => false,
+        });
+
+        if !covered {
+            continue;
+        }
+
+        let mut expected = vec![];
+        sum_terms(instantiate(&expr, &substitution, cartan)?, &mut expected);
+        let expected = sum([expected, leftover].concat());
+
+        if canonical(&expected) == canonical(&step.after) {
+            return Ok(());
+        }
+
+        return Err(format!("the rule gives {} instead", AST::from_value(&expected)));
+    }
+
+    return Err("the rule's pattern is not the term rewritten".to_string());
+}
+
+/// Whether `after` is `before` with its two numbers added or multiplied, or
+/// its scalar moved to the left.
+fn arithmetic_step(step: &Step) -> Result<(), String> {
+    let expected = match &step.before {
+        Value::Add(a, b) => a.into_number().zip(b.into_number()).map(|(a, b)| number(a + b)),
+        Value::Mul(a, b) => match (a.into_number(), b.into_number()) {
+            (Some(a), Some(b)) => Some(number(a * b)),
+            (None, Some(_)) => Some(Value::Mul(b.clone(), a.clone())),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    match expected {
+        Some(expected) if canonical(&expected) == canonical(&step.after) => return Ok(()),
+        Some(_) => return Err("the arithmetic is wrong".to_string()),
+        None => return Err("there is no arithmetic to do".to_string()),
+    }
+}
+
+/// Whether `after` is `before` with its terms collected.
+fn collect_step(step: &Step) -> Result<(), String> {
+    let collected = crate::is_lots_of_es_zero(step.before.clone(), Coefficients::Integers).map_err(|err| err.to_string())?;
+
+    if canonical(&collected) != canonical(&step.after) {
+        return Err(format!("collecting gives {} instead", AST::from_value(&collected)));
+    }
+
+    return Ok(());
+}
+
+/// Replays `trace` against `rules`: every step has to rewrite the subterm
+/// at its position with its rule, and the last term has to be the result
+/// once terms are collected. Returns why the first bad step is bad.
+///
+/// This does not use the matcher or `rewrite`, only substitution, so that
+/// trusting a result takes reading this file rather than the simplifier.
+/// Collecting terms, in a step or at the end, is the one thing it leaves to
+/// the crate.
+pub fn check(trace: &Trace, rules: &[Rule], cartan: &CartanMatrix) -> Result<(), String> {
+    let mut term = trace.start.clone();
+
+    for (n, step) in trace.steps.iter().enumerate() {
+        let at = show_position(&step.position);
+        let fail = |reason: String| format!("Step {}, at {at}: {reason}", n + 1);
+
+        match subterm(&term, &step.position) {
+            Some(found) if canonical(found) == canonical(&step.before) => {}
+            Some(found) => return Err(fail(format!("the term there is {}, not {}", AST::from_value(found), AST::from_value(&step.before)))),
+            None => return Err(fail("there is no such position".to_string())),
+        }
+
+        match step.action {
+            Action::Rule(i) => {
+                let rule = rules.get(i).ok_or_else(|| fail(format!("there is no rule {i}")))?;
+                rule_step(rule, step, cartan).map_err(|reason| fail(format!("{rule}: {reason}")))?;
+            }
+            Action::Arithmetic => arithmetic_step(step).map_err(fail)?,
+            Action::Collect => collect_step(step).map_err(fail)?,
+        }
+
+        term = replace_at(&term, &step.position, &step.after).ok_or_else(|| fail("there is no such position".to_string()))?;
+    }
+
+    if canonical(&term) == canonical(&trace.result) {
+        return Ok(());
+    }
+
+    if crate::is_lots_of_es_zero(term.clone(), Coefficients::Integers).is_ok_and(|collected| canonical(&collected) == canonical(&trace.result)) {
+        return Ok(());
+    }
+
+    return Err(format!("The steps end in {}, not {}", AST::from_value(&term), AST::from_value(&trace.result)));
+}
+
+///////
+
+fn value(src: &str, cartan: &CartanMatrix) -> Result<Value, String> {
+    let ast = parser::parse(src).map_err(|err| err.to_string())?;
+    return instantiate(&ast, &HashMap::new(), cartan);
+}
+
+fn position(src: &str) -> Result<Vec<usize>, String> {
+    if src == "root" {
+        return Ok(vec![]);
+    }
+
+    return src.split('.').map(|i| i.parse().map_err(|_| format!("Unknown position {src}"))).collect();
+}
+
+/// Splits `src` at the commas that are not inside brackets or parentheses.
+fn split_top_level(src: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let (mut depth, mut start) = (0, 0);
+
+    for (i, chr) in src.char_indices() {
+        match chr {
+            '[' | '(' => depth += 1,
+            ']' | ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(src[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    parts.push(src[start..].trim());
+
+    return parts;
+}
+
+/// A step from its header line and the terms before and after it.
+fn step(src: &str, before: &str, after: &str, cartan: &CartanMatrix) -> Result<Step, String> {
+    let (head, bindings) = src.split_once(" with ").unwrap_or((src, ""));
+
+    let (rule, at) = head.split_once(" at ").ok_or(format!("Expected a step, got {src}"))?;
+
+    let action = match rule.strip_prefix("rule ") {
+        Some(i) => Action::Rule(i.parse().map_err(|_| format!("Unknown rule {i}"))?),
+        None if rule == "arithmetic" => Action::Arithmetic,
+        None if rule == "collect" => Action::Collect,
+        None => return Err(format!("Expected a step, got {src}")),
+    };
+
+    let mut substitution = vec![];
+
+    if !bindings.is_empty() {
+        for binding in split_top_level(bindings) {
+            let (name, bound) = binding.split_once(" = ").ok_or(format!("Expected a binding, got {binding}"))?;
+            substitution.push((name.to_string(), value(bound, cartan)?));
+        }
+    }
+
+    let (before, after) = (value(before.trim(), cartan)?, value(after.trim(), cartan)?);
+
+    return Ok(Step { action, position: position(at)?, substitution, before, after });
+}
+
+/// Reads a trace back from the way `Trace` prints it.
+pub fn read(src: &str, cartan: &CartanMatrix) -> Result<Trace, String> {
+    let mut lines = src.lines().filter(|line| !line.trim().is_empty());
+
+    let start = lines.next().and_then(|line| line.strip_prefix("start ")).ok_or("Expected start")?;
+    let start = value(start, cartan)?;
+
+    let mut steps = vec![];
+
+    loop {
+        let line = lines.next().ok_or("Expected a step or the result")?;
+
+        if let Some(result) = line.strip_prefix("result ") {
+            return Ok(Trace { start, steps, result: value(result, cartan)? });
+        }
+
+        let before = lines.next().ok_or("Expected the term before a step")?;
+        let after = lines.next().ok_or("Expected the term after a step")?;
+
+        steps.push(step(line, before, after, cartan)?);
+    }
+}
+
+/// Checks the certificate at `path` against `rules`.
+pub fn report(path: &str, rules: &RuleSet, cartan: &CartanMatrix) -> Result<Verdict, ProofError> {
+    let src = std::fs::read_to_string(path).map_err(|source| ProofError::Io { path: path.to_string(), source })?;
+
+    let checked = read(&src, cartan).and_then(|trace| {
+        check(&trace, rules, cartan)?;
+        return Ok(trace);
+    });
+
+    match checked {
+        Ok(trace) => {
+            let text = format!("{} steps check, {} = {}\n", trace.steps.len(), AST::from_value(&trace.start), AST::from_value(&trace.result));
+            return Ok(Verdict { holds: true, text });
+        }
+        Err(reason) => return Ok(Verdict { holds: false, text: format!("Certificate does not check: {reason}\n") }),
+    }
+}
+
+///////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{simplify_traced, str_build, Scope};
+    use std::sync::Arc;
+
+    fn traced(src: &str) -> (Trace, RuleSet, Arc<CartanMatrix>) {
+        let cartan = Arc::new(CartanMatrix::load("./src/matrix").unwrap());
+        let rules = RuleSet::load("./src/map", &cartan).unwrap();
+        let trace = simplify_traced(str_build(src, &cartan).unwrap(), &rules, &Scope::new(&cartan)).unwrap();
+
+        return (trace, rules, cartan);
+    }
+
+    #[test]
+    fn certificates_from_simplify_check() {
+        let (trace, rules, cartan) = traced("[[E(1), E(2)], [F(1), F(3)]]");
+
+        assert!(trace.steps.len() > 5);
+        assert_eq!(check(&trace, &rules, &cartan), Ok(()));
+        assert_eq!(check(&read(&trace.to_string(), &cartan).unwrap(), &rules, &cartan), Ok(()));
+    }
+
+    #[test]
+    fn certificates_read_back_as_written() {
+        let (trace, _, cartan) = traced("[[E(1), E(2)], [F(1), F(3)]]");
+        let read = read(&trace.to_string(), &cartan).unwrap();
+
+        assert_eq!(read.to_string(), trace.to_string());
+        assert_eq!(read.steps.len(), trace.steps.len());
+
+        assert!(super::read("result 0", &cartan).is_err());
+        assert!(super::read("start E(1)\nrule 0 at root\nE(1)\n", &cartan).is_err());
+        assert!(super::read("start E(1)\nrewrite at root\nE(1)\nE(1)\nresult E(1)", &cartan).is_err());
+    }
+
+    #[test]
+    fn tampered_steps_are_rejected() {
+        let (trace, rules, cartan) = traced("[[E(1), E(2)], [F(1), F(3)]]");
+        let n = trace.steps.iter().position(|step| matches!(step.action, Action::Rule(_))).unwrap();
+
+        let mut wrong = trace.clone();
+        wrong.steps[n].after = Value::Negative(Box::new(wrong.steps[n].after.clone()));
+        assert!(check(&wrong, &rules, &cartan).unwrap_err().starts_with(&format!("Step {}", n + 1)));
+
+        let mut wrong = trace.clone();
+        let Action::Rule(i) = wrong.steps[n].action else { unreachable!() };
+        wrong.steps[n].action = Action::Rule(i + 1);
+        assert!(check(&wrong, &rules, &cartan).is_err());
+
+        wrong.steps[n].action = Action::Rule(rules.len());
+        assert!(check(&wrong, &rules, &cartan).unwrap_err().ends_with(&format!("there is no rule {}", rules.len())));
+    }
+
+    #[test]
+    fn steps_whose_guard_fails_are_rejected() {
+        let (trace, rules, cartan) = traced("[E(1), F(2)]");
+        let (n, i) = trace.steps.iter().enumerate()
+            .find_map(|(n, step)| match step.action {
+                Action::Rule(i) if !rules[i].guards.is_empty() => Some((n, i)),
+                _ => None,
+            })
+            .expect("[E(a), F(b)] = 0 if a != b fires");
+
+        assert_eq!(check(&trace, &rules, &cartan), Ok(()));
+
+        // The same step, against the rule with its guard the other way round.
+        let mut flipped = rules.to_vec();
+        flipped[i].guards = flipped[i].guards.iter()
+            .map(|guard| match guard {
+                Guard::NotEqual(a, b) => Guard::Equal(a.clone(), b.clone()),
+                Guard::Equal(a, b) => Guard::NotEqual(a.clone(), b.clone()),
+            })
+            .collect();
+
+        let reason = check(&trace, &flipped, &cartan).unwrap_err();
+        assert!(reason.starts_with(&format!("Step {}", n + 1)) && reason.contains("does not hold"), "{reason}");
+    }
+}
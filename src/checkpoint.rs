@@ -0,0 +1,136 @@
+use crate::cartan::CartanMatrix;
+use crate::ring::{Coefficients, Ring};
+use crate::search::{field, Progress, Search};
+
+/// A search saved to disk: what it was searching, the counts printed so far
+/// and how far it had got. Only searches with `Walk` are saved, not those
+/// with the rewrite rules.
+pub struct Checkpoint<R: Ring> {
+    pub cartan: CartanMatrix,
+    pub coefficients: Coefficients,
+    pub search: Search,
+    pub checked: u64,
+    pub found: u64,
+    /// The longest word to look at, which a resumed search has to keep.
+    pub max_depth: Option<usize>,
+    pub progress: Progress<R>,
+}
+
+impl<R: Ring> Checkpoint<R> {
+    pub fn write(&self) -> String {
+        return format!(
+            "ring {}\nsearch {}\nchecked {}\nfound {}\nmax-depth {}\nmatrix {}\n{}{}",
+            self.coefficients,
+            self.search,
+            self.checked,
+            self.found,
+            self.max_depth.map_or("none".to_string(), |max| max.to_string()),
+            self.cartan.rank(),
+            self.cartan,
+            self.progress.write(),
+        );
+    }
+
+    /// Saves through a temporary file, so a crash half way through a save
+    /// still leaves the previous checkpoint whole.
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        let tmp = format!("{path}.tmp");
+
+        std::fs::write(&tmp, self.write())?;
+
+        return std::fs::rename(&tmp, path);
+    }
+
+    /// Reads a checkpoint written by `write`, whose progress is of the kind
+    /// its search makes.
+    pub fn read(src: &str, ring: &R) -> Option<Checkpoint<R>> {
+        let mut lines = src.lines();
+
+        let coefficients = coefficients(lines.next()?)?;
+        let search = field(lines.next()?, "search")?.parse().ok()?;
+        let checked = field(lines.next()?, "checked")?.parse().ok()?;
+        let found = field(lines.next()?, "found")?.parse().ok()?;
+        let max_depth = match field(lines.next()?, "max-depth")? {
+            "none" => None,
+            max => Some(max.parse().ok()?),
+        };
+
+        let rank = field(lines.next()?, "matrix")?.parse::<usize>().ok()?;
+        let rows = lines.by_ref().take(rank).collect::<Vec<&str>>();
+        let cartan = CartanMatrix::parse(&rows.join("\n")).ok()?;
+
+        if cartan.rank() != rank {
+            return None;
+        }
+
+        let progress = Progress::read(&mut lines, &cartan, ring)?;
+
+        if !progress.fits(&search) {
+            return None;
+        }
+
+        return Some(Checkpoint { cartan, coefficients, search, checked, found, max_depth, progress });
+    }
+}
+
+/// The coefficient ring of a saved search, which has to be known to pick
+/// the type to read the rest of it with.
+pub fn coefficients(src: &str) -> Option<Coefficients> {
+    return field(src.lines().next()?, "ring")?.parse().ok();
+}
+
+///////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ring::Integers;
+    use crate::search::Walk;
+
+    fn cycle() -> Checkpoint<Integers> {
+        let cartan = CartanMatrix::parse("2 -1 -1\n-1 2 -2\n-1 -1 2").unwrap();
+        let walk = Walk::along(&[1, 2, 3, 1], &cartan, &Integers).unwrap();
+
+        return Checkpoint {
+            cartan,
+            coefficients: Coefficients::Integers,
+            search: "cycle:1,2,3".parse().unwrap(),
+            checked: 3,
+            found: 0,
+            max_depth: Some(9),
+            progress: Progress::Periodic(walk),
+        };
+    }
+
+    #[test]
+    fn checkpoints_read_back_as_written() {
+        let src = cycle().write();
+        let read = Checkpoint::read(&src, &Integers).unwrap();
+
+        assert_eq!(read.write(), src);
+        assert_eq!((read.search, read.checked, read.max_depth), (Search::Periodic(vec![1, 2, 3]), 3, Some(9)));
+
+        let Progress::Periodic(walk) = read.progress else {
+            panic!("Progress is not from a periodic search!");
+        };
+        assert_eq!(walk.word(), [1, 2, 3, 1]);
+    }
+
+    #[test]
+    fn progress_has_to_fit_the_search() {
+        let src = cycle().write().replace("search cycle:1,2,3", "search all:7");
+        assert!(Checkpoint::read(&src, &Integers).is_none());
+
+        let src = cycle().write().replace("max-depth 9", "max-depth none");
+        assert_eq!(Checkpoint::read(&src, &Integers).unwrap().max_depth, None);
+    }
+
+    #[test]
+    fn generators_have_to_be_in_range() {
+        let src = cycle().write();
+        let line = src.lines().find(|line| line.starts_with("nx ")).unwrap();
+        let src = src.replace(line, "nx 1:E:1,4");
+
+        assert!(Checkpoint::read(&src, &Integers).is_none());
+    }
+}
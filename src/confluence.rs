@@ -0,0 +1,683 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::cartan::CartanMatrix;
+use crate::error::ProofError;
+use crate::integer::Integer;
+use crate::parser::{Guard, Rule, AST};
+use crate::ring::Coefficients;
+use crate::value::*;
+use crate::termination;
+use crate::{ast_build, guards_hold, reduce, rewrite, sum_terms, Budget, RuleSet, Scope, Verdict, MAX_STEPS};
+
+/// A rule the way `simplify` tries it: as written or, for a bracket
+/// pattern, flipped with its result negated.
+#[derive(Clone)]
+pub(crate) struct Oriented {
+    /// Index into the rule list.
+    pub rule: usize,
+    pub pattern: AST,
+    pub expr: AST,
+    pub guards: Vec<Guard>,
+    /// Whether this is a bracket pattern the other way round.
+    pub flipped: bool,
+}
+
+pub(crate) fn oriented(rules: &[Rule]) -> Vec<Oriented> {
+    let mut out = vec![];
+
+    for (i, rule) in rules.iter().enumerate() {
+        let pattern = structural(&rule.pattern);
+
+        if let Some(flipped) = pattern.flip() {
+            out.push(Oriented { rule: i, pattern: flipped, expr: rule.expr.negate(), guards: rule.guards.clone(), flipped: true });
+        }
+
+        out.push(Oriented { rule: i, pattern, expr: rule.expr.clone(), guards: rule.guards.clone(), flipped: false });
+    }
+
+    return out;
+}
+
+/// The tree with every value in it spelled out, so that the Serre relations
+/// overlap like any other pattern.
+fn structural(ast: &AST) -> AST {
+    match ast {
+        AST::Value(value) => return AST::from_value(value),
+        AST::Braket(a, b) => return AST::Braket(Box::new(structural(a)), Box::new(structural(b))),
+        AST::Add(a, b) => return AST::Add(Box::new(structural(a)), Box::new(structural(b))),
+        AST::Mul(a, b) => return AST::Mul(Box::new(structural(a)), Box::new(structural(b))),
+        AST::Negative(a) => return AST::Negative(Box::new(structural(a))),
+        _ => return ast.clone(),
+    }
+}
+
+fn rename(ast: &AST, suffix: &str) -> AST {
+    return rename_with(ast, &mut |name| format!("{name}{suffix}"));
+}
+
+fn rename_with(ast: &AST, to: &mut dyn FnMut(&str) -> String) -> AST {
+    match ast {
+        AST::Named(name) => return AST::Named(to(name)),
+        AST::Kind(name, index) => return AST::Kind(name.clone(), to(index)),
+        AST::Value(_) => return ast.clone(),
+        AST::Braket(a, b) => return AST::Braket(Box::new(rename_with(a, to)), Box::new(rename_with(b, to))),
+        AST::Add(a, b) => return AST::Add(Box::new(rename_with(a, to)), Box::new(rename_with(b, to))),
+        AST::Mul(a, b) => return AST::Mul(Box::new(rename_with(a, to)), Box::new(rename_with(b, to))),
+        AST::C(a, b) => return AST::C(Box::new(rename_with(a, to)), Box::new(rename_with(b, to))),
+        AST::Negative(a) => return AST::Negative(Box::new(rename_with(a, to))),
+    }
+}
+
+/// The rule with its variables named `x1`, `x2` and so on in the order they
+/// come in, clear of the suffix `overlaps` gives the variables of the rule
+/// inside.
+fn fresh(rule: Rule) -> Rule {
+    let mut names = HashMap::new();
+    let mut to = |name: &str| {
+        let count = names.len();
+        return names.entry(name.to_string()).or_insert_with(|| format!("x{}", count + 1)).clone();
+    };
+
+    let pattern = rename_with(&rule.pattern, &mut to);
+    let expr = rename_with(&rule.expr, &mut to);
+
+    return Rule { pattern, expr, ..rule };
+}
+
+fn map_guards(guards: &[Guard], f: impl Fn(&AST) -> AST) -> Vec<Guard> {
+    return guards
+        .iter()
+        .map(|guard| match guard {
+            Guard::Equal(a, b) => Guard::Equal(f(a), f(b)),
+            Guard::NotEqual(a, b) => Guard::NotEqual(f(a), f(b)),
+        })
+        .collect();
+}
+
+///////
+
+/// Variables of both rules, a generator index bound to a number or another
+/// index.
+type Substitution = HashMap<String, AST>;
+
+/// Follows bindings until the tree is not a bound variable.
+fn walk(ast: &AST, sigma: &Substitution) -> AST {
+    let mut ast = ast.clone();
+
+    while let AST::Named(name) = &ast {
+        match sigma.get(name) {
+            Some(bound) => ast = bound.clone(),
+            None => break,
+        }
+    }
+
+    return ast;
+}
+
+fn occurs(name: &str, ast: &AST, sigma: &Substitution) -> bool {
+    match walk(ast, sigma) {
+        AST::Named(other) => return other == name,
+        AST::Kind(_, index) => return occurs(name, &AST::Named(index), sigma),
+        AST::Value(_) => return false,
+        AST::Braket(a, b) | AST::Add(a, b) | AST::Mul(a, b) | AST::C(a, b) => {
+            return occurs(name, &a, sigma) || occurs(name, &b, sigma);
+        }
+        AST::Negative(a) => return occurs(name, &a, sigma),
+    }
+}
+
+/// Syntactic unification. Sums are unified as written, so two patterns that
+/// only overlap up to the order of the terms of a sum are not found.
+fn unify(a: &AST, b: &AST, sigma: &mut Substitution) -> bool {
+    let a = walk(a, sigma);
+    let b = walk(b, sigma);
+
+    match (&a, &b) {
+        (AST::Named(x), AST::Named(y)) if x == y => return true,
+        (AST::Named(x), t) | (t, AST::Named(x)) => {
+            if occurs(x, t, sigma) {
+                return false;
+            }
+
+            sigma.insert(x.clone(), t.clone());
+            return true;
+        }
+        (AST::Kind(n, i), AST::Kind(m, j)) => {
+            return n == m && unify(&AST::Named(i.clone()), &AST::Named(j.clone()), sigma);
+        }
+        (AST::Kind(n, i), AST::Value(Value::Kind(m, k))) | (AST::Value(Value::Kind(m, k)), AST::Kind(n, i)) => {
+            return n == m && unify(&AST::Named(i.clone()), &AST::Value(Value::Number(Integer::from(*k))), sigma);
+        }
+        (AST::Value(u), AST::Value(v)) => return u == v,
+        (AST::Braket(a1, b1), AST::Braket(a2, b2))
+        | (AST::Add(a1, b1), AST::Add(a2, b2))
+        | (AST::Mul(a1, b1), AST::Mul(a2, b2)) => return unify(a1, a2, sigma) && unify(b1, b2, sigma),
+        (AST::Negative(a), AST::Negative(b)) => return unify(a, b, sigma),
+        _ => return false,
+    }
+}
+
+fn substitute(ast: &AST, sigma: &Substitution) -> Result<AST, ProofError> {
+    let both = |a: &AST, b: &AST| -> Result<(Box<AST>, Box<AST>), ProofError> {
+        return Ok((Box::new(substitute(a, sigma)?), Box::new(substitute(b, sigma)?)));
+    };
+
+    match ast {
+        AST::Named(name) => match sigma.get(name) {
+            Some(bound) => return substitute(bound, sigma),
+            None => return Ok(ast.clone()),
+        },
+        AST::Kind(name, index) => match substitute(&AST::Named(index.clone()), sigma)? {
+            AST::Named(index) => return Ok(AST::Kind(name.clone(), index)),
+            bound => {
+                let n = match &bound {
+                    AST::Value(Value::Number(n)) => n.to_i32(),
+                    _ => None,
+                };
+
+                // An index variable unified with a bracket, or with a number
+                // no generator has.
+                let Some(n) = n else {
+                    return Err(ProofError::Mismatch { what: format!("{name}({index})"), expected: "a generator index", found: bound.to_string() });
+                };
+
+                return Ok(AST::Value(Value::Kind(name.clone(), n)));
+            }
+        },
+        AST::Value(_) => return Ok(ast.clone()),
+        AST::Braket(a, b) => return both(a, b).map(|(a, b)| AST::Braket(a, b)),
+        AST::Add(a, b) => return both(a, b).map(|(a, b)| AST::Add(a, b)),
+        AST::Mul(a, b) => return both(a, b).map(|(a, b)| AST::Mul(a, b)),
+        AST::C(a, b) => return both(a, b).map(|(a, b)| AST::C(a, b)),
+        AST::Negative(a) => return Ok(AST::Negative(Box::new(substitute(a, sigma)?))),
+    }
+}
+
+fn substitute_guards(guards: &[Guard], sigma: &Substitution) -> Result<Vec<Guard>, ProofError> {
+    return guards
+        .iter()
+        .map(|guard| match guard {
+            Guard::Equal(a, b) => Ok(Guard::Equal(substitute(a, sigma)?, substitute(b, sigma)?)),
+            Guard::NotEqual(a, b) => Ok(Guard::NotEqual(substitute(a, sigma)?, substitute(b, sigma)?)),
+        })
+        .collect();
+}
+
+///////
+
+fn children(ast: &AST) -> Vec<&AST> {
+    match ast {
+        AST::Braket(a, b) | AST::Add(a, b) | AST::Mul(a, b) => return vec![a, b],
+        AST::Negative(a) => return vec![a],
+        _ => return vec![],
+    }
+}
+
+/// The positions of every subtree that is not a variable, as the child
+/// taken at each step down from the root.
+fn positions(ast: &AST) -> Vec<Vec<usize>> {
+    if let AST::Named(_) = ast {
+        return vec![];
+    }
+
+    let mut out = vec![vec![]];
+
+    for (i, child) in children(ast).into_iter().enumerate() {
+        out.extend(positions(child).into_iter().map(|position| [vec![i], position].concat()));
+    }
+
+    return out;
+}
+
+fn at<'a>(ast: &'a AST, position: &[usize]) -> &'a AST {
+    match position.split_first() {
+        Some((i, rest)) => return at(children(ast)[*i], rest),
+        None => return ast,
+    }
+}
+
+fn replace(ast: &AST, position: &[usize], with: &AST) -> AST {
+    let Some((i, rest)) = position.split_first() else {
+        return with.clone();
+    };
+
+    let child = |j: usize, child: &AST| Box::new(if j == *i { replace(child, rest, with) } else { child.clone() });
+
+    match ast {
+        AST::Braket(a, b) => return AST::Braket(child(0, a), child(1, b)),
+        AST::Add(a, b) => return AST::Add(child(0, a), child(1, b)),
+        AST::Mul(a, b) => return AST::Mul(child(0, a), child(1, b)),
+        AST::Negative(a) => return AST::Negative(child(0, a)),
+        _ => panic!("No position {position:?} in {ast}!"),
+    }
+}
+
+///////
+
+/// A term both an `outer` rule, at the root, and an `inner` rule, at
+/// `position`, apply to, with what each of them rewrites it to.
+struct Overlap {
+    outer: usize,
+    inner: usize,
+    peak: AST,
+    left: AST,
+    right: AST,
+    /// The guards of both rules, since both have to apply.
+    guards: Vec<Guard>,
+}
+
+/// Every critical pair of the rules, flipped bracket patterns included.
+fn overlaps(rules: &[Rule]) -> Result<Vec<Overlap>, ProofError> {
+    let oriented = oriented(rules);
+    let mut out = vec![];
+
+    for (i, outer) in oriented.iter().enumerate() {
+        for (j, inner) in oriented.iter().enumerate() {
+            // Variables of the inner rule get a suffix, to keep the two
+            // rules' variables apart.
+            let pattern = rename(&inner.pattern, "_");
+            let expr = rename(&inner.expr, "_");
+
+            for position in positions(&outer.pattern) {
+                // A rule overlaps itself trivially at the root, and two
+                // rules overlapping at the root are one pair, not two.
+                if position.is_empty() && j <= i {
+                    continue;
+                }
+
+                let mut sigma = Substitution::new();
+
+                if !unify(at(&outer.pattern, &position), &pattern, &mut sigma) {
+                    continue;
+                }
+
+                let peak = substitute(&outer.pattern, &sigma)?;
+
+                if !well_sorted(&peak, false) {
+                    continue;
+                }
+
+                let guards = [outer.guards.clone(), map_guards(&inner.guards, |ast| rename(ast, "_"))].concat();
+
+                out.push(Overlap {
+                    outer: outer.rule,
+                    inner: inner.rule,
+                    peak,
+                    left: substitute(&outer.expr, &sigma)?,
+                    right: substitute(&replace(&outer.pattern, &position, &expr), &sigma)?,
+                    guards: substitute_guards(&guards, &sigma)?,
+                });
+            }
+        }
+    }
+
+    return Ok(out);
+}
+
+/// Whether every coefficient, on the left of a product, is free of
+/// generators. Unifying a coefficient variable with a bracket gives terms
+/// no rule is ever applied to.
+fn well_sorted(ast: &AST, scalar: bool) -> bool {
+    match ast {
+        AST::Kind(_, _) | AST::Value(Value::Kind(_, _)) | AST::Braket(_, _) if scalar => return false,
+        AST::Mul(a, b) => return well_sorted(a, true) && well_sorted(b, scalar),
+        _ => return children(ast).into_iter().all(|child| well_sorted(child, scalar)),
+    }
+}
+
+/// Every variable of the tree, and whether it is a generator index, in
+/// `E(a)` or `C(a, b)`.
+fn variables(ast: &AST, out: &mut HashMap<String, bool>) {
+    match ast {
+        AST::Named(name) => {
+            out.entry(name.clone()).or_insert(false);
+        }
+        AST::Kind(_, index) => {
+            out.insert(index.clone(), true);
+        }
+        AST::C(a, b) => {
+            for index in [a, b] {
+                if let AST::Named(name) = index.as_ref() {
+                    out.insert(name.clone(), true);
+                }
+            }
+        }
+        AST::Value(_) => {}
+        AST::Braket(a, b) | AST::Add(a, b) | AST::Mul(a, b) => {
+            variables(a, out);
+            variables(b, out);
+        }
+        AST::Negative(a) => variables(a, out),
+    }
+}
+
+/// Binds each variable to each of its values in turn, calling `k` until it
+/// returns false. Returns whether every call returned true.
+fn each_instance(vars: &[(String, Vec<Value>)], scope: &mut Scope, k: &mut dyn FnMut(&Scope) -> bool) -> bool {
+    let Some(((name, values), rest)) = vars.split_first() else {
+        return k(scope);
+    };
+
+    for value in values {
+        scope.set(name, value.clone());
+
+        if !each_instance(rest, scope, k) {
+            return false;
+        }
+    }
+
+    return true;
+}
+
+/// A variable held fixed as a generator no rule names, so that a pair that
+/// joins with it in joins for every term the variable stands for. Generator
+/// indices start at 1, which tells these apart.
+fn constant(name: &str) -> Value {
+    return Value::Kind(name.to_string(), 0);
+}
+
+/// The term with its constants back as the variables they stand for.
+fn symbolic(value: &Value) -> AST {
+    match value {
+        Value::Kind(name, 0) => return AST::Named(name.clone()),
+        Value::Braket(a, b) => return AST::Braket(Box::new(symbolic(a)), Box::new(symbolic(b))),
+        Value::Add(a, b) => return AST::Add(Box::new(symbolic(a)), Box::new(symbolic(b))),
+        Value::Mul(a, b) => return AST::Mul(Box::new(symbolic(a)), Box::new(symbolic(b))),
+        Value::Negative(a) => return AST::Negative(Box::new(symbolic(a))),
+        Value::Number(_) | Value::Kind(_, _) => return AST::Value(value.clone()),
+    }
+}
+
+/// The term written with the terms of every sum in order, so that sums that
+/// only differ in the order and grouping of their terms write the same, the
+/// way matching sees them.
+fn sorted(value: &Value) -> String {
+    match value {
+        Value::Add(_, _) => {
+            let mut terms = sum_terms(value).iter().map(sorted).collect::<Vec<String>>();
+            terms.sort();
+            return format!("({})", terms.join(" + "));
+        }
+        Value::Braket(a, b) => return format!("[{}, {}]", sorted(a), sorted(b)),
+        Value::Mul(a, b) => return format!("({} * {})", sorted(a), sorted(b)),
+        Value::Negative(a) => return format!("-{}", sorted(a)),
+        Value::Number(_) | Value::Kind(_, _) => return value.to_string(),
+    }
+}
+
+///////
+
+/// A critical pair whose two sides rewrite to different normal forms.
+pub struct Unjoinable {
+    /// Indices into the rule list.
+    pub outer: usize,
+    pub inner: usize,
+    /// The first instance found that does not join, and what the two rules
+    /// rewrite it to. Only generator indices are filled in.
+    pub peak: AST,
+    pub rewrites: (AST, AST),
+    /// What the rules rewrite each side to, None where they do not
+    /// terminate.
+    pub left: Option<AST>,
+    pub right: Option<AST>,
+    /// Whether the two sides do meet once terms are collected in the Lyndon
+    /// basis as well, the way `simplify` goes on.
+    pub collected: bool,
+}
+
+/// `crate::normal_form` over the integers for rules that need not be a
+/// `RuleSet`. None where it fails.
+pub(crate) fn normal_form(value: Value, rules: &Vec<Rule>, cartan: &Arc<CartanMatrix>) -> Option<Value> {
+    return reduce(value, rules, cartan, Coefficients::Integers, &mut Budget::new(MAX_STEPS)).ok();
+}
+
+/// Whether the two sides are equal once terms are collected too, by their
+/// difference simplifying to zero. Comparing normal forms instead would
+/// tell apart sums that only differ in the order of terms.
+fn joins(pair: &(Value, Value), rules: &Vec<Rule>, cartan: &Arc<CartanMatrix>) -> bool {
+    let difference = Value::Add(Box::new(pair.0.clone()), Box::new(Value::Negative(Box::new(pair.1.clone()))));
+
+    return normal_form(difference, rules, cartan) == Some(Value::zero());
+}
+
+/// `rewrite` with nothing else, terms not collected. None where it fails.
+fn rewritten(value: Value, rules: &Vec<Rule>, cartan: &Arc<CartanMatrix>) -> Option<Value> {
+    return rewrite(value, rules, cartan, &mut Budget::new(MAX_STEPS)).ok();
+}
+
+/// The first instance of an overlap whose two sides the rules rewrite to
+/// different terms, sums compared up to the order of their terms.
+///
+/// Guards and `C(a, b)` need generator indices as numbers, so indices run
+/// over every generator of the matrix. Any other variable is a constant no
+/// rule names, which checks the overlap for every term at once.
+fn check(overlap: &Overlap, rules: &Vec<Rule>, cartan: &Arc<CartanMatrix>) -> Result<Option<Unjoinable>, ProofError> {
+    let mut found = HashMap::new();
+
+    for ast in [&overlap.peak, &overlap.left, &overlap.right] {
+        variables(ast, &mut found);
+    }
+
+    let mut found = found.into_iter().collect::<Vec<(String, bool)>>();
+    found.sort();
+
+    let vars = found
+        .into_iter()
+        .map(|(name, index)| match index {
+            true => (name, (1..=cartan.rank() as i32).map(|i| Value::Number(Integer::from(i))).collect()),
+            false => {
+                let constant = constant(&name);
+                (name, vec![constant])
+            }
+        })
+        .collect::<Vec<(String, Vec<Value>)>>();
+
+    let guarded = Rule { guards: overlap.guards.clone(), ..Rule::new(overlap.peak.clone(), overlap.left.clone()) };
+    let mut unjoinable = None;
+
+    let mut instance = |scope: &Scope| -> Result<bool, ProofError> {
+        if !guards_hold(&guarded, scope)? {
+            return Ok(true);
+        }
+
+        let rewrites = (ast_build(&overlap.left, scope)?, ast_build(&overlap.right, scope)?);
+        let left = rewritten(rewrites.0.clone(), rules, cartan);
+        let right = rewritten(rewrites.1.clone(), rules, cartan);
+
+        if let (Some(left), Some(right)) = (&left, &right) {
+            if sorted(left) == sorted(right) {
+                return Ok(true);
+            }
+        }
+
+        unjoinable = Some(Unjoinable {
+            outer: overlap.outer,
+            inner: overlap.inner,
+            peak: symbolic(&ast_build(&overlap.peak, scope)?),
+            rewrites: (symbolic(&rewrites.0), symbolic(&rewrites.1)),
+            left: left.as_ref().map(symbolic),
+            right: right.as_ref().map(symbolic),
+            collected: joins(&rewrites, rules, cartan),
+        });
+
+        return Ok(false);
+    };
+
+    let mut failed = None;
+
+    each_instance(&vars, &mut Scope::new(cartan), &mut |scope| match instance(scope) {
+        Ok(more) => more,
+        Err(err) => {
+            failed = Some(err);
+            false
+        }
+    });
+
+    if let Some(err) = failed {
+        return Err(err);
+    }
+
+    return Ok(unjoinable);
+}
+
+/// The critical pairs of the rules that do not join, and how many critical
+/// pairs there are in all.
+pub fn critical_pairs(rules: &Vec<Rule>, cartan: &Arc<CartanMatrix>) -> Result<(Vec<Unjoinable>, usize), ProofError> {
+    let overlaps = overlaps(rules)?;
+    let mut unjoinable = vec![];
+
+    for overlap in &overlaps {
+        let Some(pair) = check(overlap, rules, cartan)? else {
+            continue;
+        };
+
+        // A flipped bracket pattern can overlap the same way as the rule
+        // as written.
+        let peak = pair.peak.to_string();
+
+        if !unjoinable.iter().any(|other: &Unjoinable| (other.outer, other.inner, other.peak.to_string()) == (pair.outer, pair.inner, peak.clone())) {
+            unjoinable.push(pair);
+        }
+    }
+
+    return Ok((unjoinable, overlaps.len()));
+}
+
+/// Knuth-Bendix completion: adds a rule for every critical pair that does
+/// not join, for up to `rounds` rounds or until every pair joins. Each rule
+/// takes the greater normal form of the pair to the other, in the path
+/// ordering that orients the most of the rules. Returns the rules added and
+/// the pairs still not joinable. A pair that does not terminate, or that
+/// the ordering cannot orient, is left as it is.
+pub fn complete(rules: &[Rule], cartan: &Arc<CartanMatrix>, rounds: usize) -> Result<(Vec<Rule>, Vec<Unjoinable>), ProofError> {
+    let mut rules = rules.to_vec();
+    let mut added = vec![];
+
+    for _ in 0..rounds {
+        let (unjoinable, _) = critical_pairs(&rules, cartan)?;
+        let ordering = termination::ordering(&rules);
+        let mut progress = false;
+
+        for pair in &unjoinable {
+            let (Some(left), Some(right)) = (&pair.left, &pair.right) else {
+                continue;
+            };
+
+            let Some(rule) = ordering.orient(left, right).map(fresh) else {
+                continue;
+            };
+
+            // Two pairs can come to the same rule.
+            if rules.iter().any(|other| other.pattern.to_string() == rule.pattern.to_string() && other.guards.is_empty()) {
+                continue;
+            }
+
+            let rule = Rule { section: "completion".to_string(), ..rule };
+            rules.push(rule.clone());
+            added.push(rule);
+            progress = true;
+        }
+
+        if !progress {
+            return Ok((added, unjoinable));
+        }
+    }
+
+    let (unjoinable, _) = critical_pairs(&rules, cartan)?;
+
+    return Ok((added, unjoinable));
+}
+
+/// The critical pairs of the rules that do not join and, given `rounds`,
+/// the rules completion proposes for them. The rules hold if every critical
+/// pair joins by rewriting, which makes them locally confluent.
+pub fn report(rules: &RuleSet, cartan: &Arc<CartanMatrix>, rounds: Option<usize>) -> Result<Verdict, ProofError> {
+    let (unjoinable, count) = critical_pairs(rules, cartan)?;
+
+    let show = |side: &Option<AST>| match side {
+        Some(ast) => ast.to_string(),
+        None => "no normal form".to_string(),
+    };
+
+    let mut text = String::new();
+
+    for pair in &unjoinable {
+        text += &format!("Not joinable: {}\n", pair.peak);
+        text += &format!("    {}\n", rules[pair.outer]);
+        text += &format!("    {}\n", rules[pair.inner]);
+        text += &format!("  gives {}\n", show(&pair.left));
+        text += &format!("    and {}\n", show(&pair.right));
+
+        if pair.collected {
+            text += "  which meet once terms are collected\n";
+        }
+    }
+
+    let collected = unjoinable.iter().filter(|pair| pair.collected).count();
+    text += &format!("{count} critical pairs, {} not joinable by rewriting, {collected} of them once terms are collected\n", unjoinable.len());
+
+    let Some(rounds) = rounds else {
+        return Ok(Verdict { holds: unjoinable.is_empty(), text });
+    };
+
+    let (added, unjoinable) = complete(rules, cartan, rounds)?;
+
+    if !added.is_empty() {
+        text += "section completion\n";
+
+        for rule in &added {
+            text += &format!("{rule}\n");
+        }
+    }
+
+    text += &format!("Completion added {} rules, {} pairs still not joinable\n", added.len(), unjoinable.len());
+
+    return Ok(Verdict { holds: unjoinable.is_empty(), text });
+}
+
+///////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn rules(src: &[(&str, &str)]) -> Vec<Rule> {
+        return src.iter().map(|(pattern, expr)| Rule::new(parse(pattern).unwrap(), parse(expr).unwrap())).collect();
+    }
+
+    fn a1() -> Arc<CartanMatrix> {
+        return Arc::new(CartanMatrix::dynkin('A', 1).unwrap());
+    }
+
+    #[test]
+    fn negation_rules_are_confluent() {
+        let rules = rules(&[("--a", "a"), ("-0", "0"), ("[H(a), H(b)]", "0")]);
+        let (unjoinable, count) = critical_pairs(&rules, &a1()).unwrap();
+
+        assert!(count > 0);
+        assert!(unjoinable.is_empty());
+    }
+
+    #[test]
+    fn a_sum_in_both_places_of_a_bracket_does_not_join() {
+        let rules = rules(&[("[a, a]", "0"), ("-0", "0"), ("[x + y, z]", "[x, z] + [y, z]")]);
+        let (unjoinable, _) = critical_pairs(&rules, &a1()).unwrap();
+
+        let pair = unjoinable.first().expect("The pair at [x + y, x + y] does not join!");
+        assert_eq!(pair.peak.to_string(), "[x_ + y_, x_ + y_]");
+        assert_eq!(pair.left.as_ref().unwrap().to_string(), "0");
+        // Flipped, the rule for sums splits a sum on the right too.
+        assert_eq!(pair.right.as_ref().unwrap().to_string(), "-(-(0 + [y_, x_]) - ([x_, y_] + 0))");
+    }
+
+    #[test]
+    fn completion_orients_the_new_rule_from_the_greater_side() {
+        let rules = rules(&[("[a, a]", "0"), ("-0", "0"), ("[x + y, z]", "[x, z] + [y, z]")]);
+        let (added, _) = complete(&rules, &a1(), 1).unwrap();
+
+        let added = added.iter().map(|rule| rule.to_string()).collect::<Vec<String>>();
+        assert_eq!(added, ["-(-(0 + [x1, x2]) - ([x2, x1] + 0)) = 0"]);
+    }
+}
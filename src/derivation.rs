@@ -0,0 +1,854 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::cartan::CartanMatrix;
+use crate::error::ProofError;
+use crate::certificate::{instantiate, Substitution};
+use crate::confluence::normal_form;
+use crate::parser::{self, Rule, AST};
+use crate::value::*;
+use crate::{RuleSet, Verdict};
+
+/// An expression of a derivation, maybe split into cases.
+pub(crate) enum Expr {
+    Plain(String),
+    /// `if n == b { x } else { y }`, or with `!=`.
+    If(String, Box<Expr>, Box<Expr>),
+}
+
+/// An expression and the line of the file it starts on.
+struct Element {
+    line: usize,
+    expr: Expr,
+}
+
+enum Item {
+    /// One case of a function of an index, `N(0) = E(0)` or
+    /// `N(n) = [N(n - 1), E(n)]`.
+    Clause { name: String, param: String, body: String },
+    /// Expressions each equal to the one before, the last assigned to
+    /// `target` if there is one.
+    Chain { target: Option<String>, elements: Vec<Element> },
+    /// `for n in a..b { ... }`, run in order with `n` from `a` up to `b - 1`.
+    For { var: String, from: i64, to: i64, body: Vec<Item> },
+}
+
+/// What went wrong, on which line.
+type Failure = (usize, String);
+
+const KEYWORDS: [&str; 5] = ["if", "else", "for", "in", "v"];
+
+/// The names `n_f` gives its terms, for derivations that use them without
+/// saying what they are.
+const PRESETS: [(&str, &str); 2] = [("nx_f", "[nx, F(b)]"), ("nx_h", "[nx, H(b)]")];
+
+fn is_generator(name: &str) -> bool {
+    return ["E", "F", "H"].contains(&name);
+}
+
+pub(crate) fn is_identifier(src: &str) -> bool {
+    let mut chars = src.chars();
+    return chars.next().is_some_and(|chr| chr.is_ascii_alphabetic()) && chars.all(|chr| chr.is_ascii_alphanumeric() || chr == '_');
+}
+
+/// Where `src` has an `=` of its own, not part of `==` or `!=`, outside
+/// brackets.
+pub(crate) fn equals_sign(src: &str) -> Option<usize> {
+    let bytes = src.as_bytes();
+    let mut depth = 0;
+
+    for (i, chr) in src.char_indices() {
+        match chr {
+            '[' | '(' | '{' => depth += 1,
+            ']' | ')' | '}' => depth -= 1,
+            '=' if depth == 0 => {
+                let before = if i > 0 { bytes[i - 1] } else { b' ' };
+                let after = bytes.get(i + 1).copied().unwrap_or(b' ');
+
+                if !b"=!<>".contains(&before) && after != b'=' {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    return None;
+}
+
+///////
+
+struct Lines {
+    lines: Vec<(usize, String)>,
+    i: usize,
+}
+
+impl Lines {
+    fn peek(&self) -> Option<&(usize, String)> {
+        return self.lines.get(self.i);
+    }
+
+    fn expect(&mut self, text: &str) -> Result<(), Failure> {
+        match self.peek() {
+            Some((_, line)) if line == text => {
+                self.i += 1;
+                return Ok(());
+            }
+            Some((number, line)) => return Err((*number, format!("expected `{text}`, got `{line}`"))),
+            None => return Err((self.lines.last().map(|line| line.0).unwrap_or(0), format!("expected `{text}`"))),
+        }
+    }
+
+    /// An expression starting on the current line, an equation `a = b` read
+    /// as `a - b`.
+    fn expr(&mut self, text: &str) -> Result<Expr, Failure> {
+        let number = self.peek().map(|line| line.0).unwrap_or(0);
+        self.i += 1;
+
+        let Some(condition) = text.strip_prefix("if ") else {
+            return match equals_sign(text) {
+                Some(i) => Ok(Expr::Plain(format!("({}) - ({})", &text[..i].trim(), &text[i + 1..].trim()))),
+                None => Ok(Expr::Plain(text.to_string())),
+            };
+        };
+
+        let condition = condition.strip_suffix('{').ok_or((number, "expected `{` after the condition".to_string()))?;
+        let then = self.branch()?;
+        self.expect("} else {")?;
+        let otherwise = self.branch()?;
+        self.expect("}")?;
+
+        return Ok(Expr::If(condition.trim().to_string(), Box::new(then), Box::new(otherwise)));
+    }
+
+    fn branch(&mut self) -> Result<Expr, Failure> {
+        let (number, text) = self.peek().cloned().ok_or((0, "expected an expression".to_string()))?;
+
+        if text.is_empty() || text.starts_with('}') {
+            return Err((number, "expected an expression".to_string()));
+        }
+
+        return self.expr(&text);
+    }
+
+    /// Items up to the end of the block, or up to a `}` if `nested`.
+    fn items(&mut self, nested: bool) -> Result<Vec<Item>, Failure> {
+        let mut items = vec![];
+        let mut chain: Option<(Option<String>, Vec<Element>)> = None;
+
+        // Whether the next expression continues the chain: after a `v`, or
+        // straight after a bare expression.
+        let mut joined = false;
+
+        let flush = |chain: &mut Option<(Option<String>, Vec<Element>)>, items: &mut Vec<Item>| {
+            if let Some((target, elements)) = chain.take() {
+                items.push(Item::Chain { target, elements });
+            }
+        };
+
+        while let Some((number, text)) = self.peek().cloned() {
+            if text.is_empty() {
+                flush(&mut chain, &mut items);
+                joined = false;
+                self.i += 1;
+                continue;
+            }
+
+            if text == "}" && nested {
+                break;
+            }
+
+            if text == "v" {
+                joined = true;
+                self.i += 1;
+                continue;
+            }
+
+            if let Some(rest) = text.strip_prefix("for ") {
+                flush(&mut chain, &mut items);
+                self.i += 1;
+
+                let range = || -> Option<(String, i64, i64)> {
+                    let (var, range) = rest.strip_suffix('{')?.split_once(" in ")?;
+                    let (from, to) = range.trim().split_once("..")?;
+                    return Some((var.trim().to_string(), from.trim().parse().ok()?, to.trim().parse().ok()?));
+                };
+
+                let (var, from, to) = range().ok_or((number, "expected `for n in a..b {`".to_string()))?;
+                let body = self.items(true)?;
+                self.expect("}")?;
+
+                items.push(Item::For { var, from, to, body });
+                joined = false;
+                continue;
+            }
+
+            let assigned = equals_sign(&text).map(|i| (text[..i].trim(), text[i + 1..].trim()));
+
+            if let Some((lhs, body)) = assigned {
+                let clause = lhs.strip_suffix(')').and_then(|lhs| lhs.split_once('('));
+
+                if let Some((name, param)) = clause.filter(|(name, param)| {
+                    name.starts_with(|chr: char| chr.is_ascii_uppercase()) && !is_generator(name) && *name != "C" && is_identifier(name)
+                        && (is_identifier(param) || param.parse::<i64>().is_ok())
+                }) {
+                    flush(&mut chain, &mut items);
+                    items.push(Item::Clause { name: name.to_string(), param: param.to_string(), body: body.to_string() });
+                    joined = false;
+                    self.i += 1;
+                    continue;
+                }
+            }
+
+            let (target, text) = match assigned {
+                Some((lhs, body)) if is_identifier(lhs) && lhs.starts_with(|chr: char| chr.is_ascii_lowercase()) => {
+                    (Some(lhs.to_string()), body.to_string())
+                }
+                _ => (None, text.clone()),
+            };
+
+            let element = Element { line: number, expr: self.expr(&text)? };
+
+            match &mut chain {
+                Some((current, elements)) if joined && *current == target => elements.push(element),
+                _ => {
+                    flush(&mut chain, &mut items);
+                    chain = Some((target.clone(), vec![element]));
+                }
+            }
+
+            joined = target.is_none();
+        }
+
+        flush(&mut chain, &mut items);
+
+        return Ok(items);
+    }
+}
+
+///////
+
+/// Integer arithmetic on indices: `+`, `-`, `*`, `%` and parentheses.
+pub(crate) fn int(src: &str, ints: &HashMap<String, i64>) -> Result<i64, String> {
+    let tokens = tokens(src);
+    let mut i = 0;
+    let value = sum_of(&tokens, &mut i, ints)?;
+
+    if i < tokens.len() {
+        return Err(format!("`{src}` is not an index"));
+    }
+
+    return Ok(value);
+}
+
+fn tokens(src: &str) -> Vec<String> {
+    let mut out: Vec<String> = vec![];
+    let mut word = false;
+
+    for chr in src.chars() {
+        if chr.is_ascii_alphanumeric() || chr == '_' {
+            match out.last_mut() {
+                Some(last) if word => last.push(chr),
+                _ => out.push(chr.to_string()),
+            }
+
+            word = true;
+        } else {
+            if !chr.is_whitespace() {
+                out.push(chr.to_string());
+            }
+
+            word = false;
+        }
+    }
+
+    return out;
+}
+
+fn sum_of(tokens: &[String], i: &mut usize, ints: &HashMap<String, i64>) -> Result<i64, String> {
+    let mut value = product_of(tokens, i, ints)?;
+
+    while let Some(op) = tokens.get(*i).filter(|op| *op == "+" || *op == "-") {
+        *i += 1;
+        let rhs = product_of(tokens, i, ints)?;
+        value = if op == "+" { value + rhs } else { value - rhs };
+    }
+
+    return Ok(value);
+}
+
+fn product_of(tokens: &[String], i: &mut usize, ints: &HashMap<String, i64>) -> Result<i64, String> {
+    let mut value = factor(tokens, i, ints)?;
+
+    while let Some(op) = tokens.get(*i).filter(|op| *op == "*" || *op == "%") {
+        *i += 1;
+        let rhs = factor(tokens, i, ints)?;
+
+        value = match op.as_str() {
+            "*" => value * rhs,
+            _ if rhs == 0 => return Err("remainder by zero".to_string()),
+            _ => value.rem_euclid(rhs),
+        };
+    }
+
+    return Ok(value);
+}
+
+fn factor(tokens: &[String], i: &mut usize, ints: &HashMap<String, i64>) -> Result<i64, String> {
+    let token = tokens.get(*i).ok_or("expected an index")?;
+    *i += 1;
+
+    if token == "-" {
+        return Ok(-factor(tokens, i, ints)?);
+    }
+
+    if token == "(" {
+        let value = sum_of(tokens, i, ints)?;
+
+        if tokens.get(*i).map(|token| token.as_str()) != Some(")") {
+            return Err("expected `)`".to_string());
+        }
+
+        *i += 1;
+        return Ok(value);
+    }
+
+    if let Ok(n) = token.parse() {
+        return Ok(n);
+    }
+
+    return ints.get(token).copied().ok_or(format!("{token} is not an index"));
+}
+
+pub(crate) fn condition(src: &str, ints: &HashMap<String, i64>) -> Result<bool, String> {
+    if let Some((a, b)) = src.split_once("==") {
+        return Ok(int(a, ints)? == int(b, ints)?);
+    }
+
+    if let Some((a, b)) = src.split_once("!=") {
+        return Ok(int(a, ints)? != int(b, ints)?);
+    }
+
+    return Err(format!("`{src}` is not a condition"));
+}
+
+///////
+
+struct Context<'a> {
+    cartan: &'a Arc<CartanMatrix>,
+    rules: &'a Vec<Rule>,
+    clauses: HashMap<String, Vec<(String, String)>>,
+    /// 1 where the file numbers generators from 0, like the node of an
+    /// affine diagram, and 0 where it numbers them from 1 like the matrix.
+    offset: i64,
+}
+
+/// 1 where `src` numbers generators from 0, 0 where it numbers them from 1.
+pub(crate) fn offset(src: &str) -> i64 {
+    return if ["E(0)", "F(0)", "H(0)"].iter().any(|zero| src.contains(zero)) { 1 } else { 0 };
+}
+
+/// The text between the brackets opening at `open`.
+fn arguments(src: &str, open: usize) -> Option<(&str, usize)> {
+    let mut depth = 0;
+
+    for (i, chr) in src[open..].char_indices() {
+        match chr {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some((&src[open + 1..open + i], open + i));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    return None;
+}
+
+/// `src` with the index arithmetic in every generator and `C(i, j)` worked
+/// out and shifted by `offset`, and every call of a function in `functions`
+/// made with `call`, its result bound in `bound` under a name of its own.
+pub(crate) fn expand(
+    src: &str,
+    ints: &HashMap<String, i64>,
+    offset: i64,
+    functions: &dyn Fn(&str) -> bool,
+    call: &dyn Fn(&str, i64) -> Result<Value, String>,
+    bound: &mut Substitution,
+) -> Result<String, String> {
+    let mut out = String::new();
+    let mut i = 0;
+
+    while i < src.len() {
+        let rest = &src[i..];
+        let word = rest.find(|chr: char| !(chr.is_ascii_alphanumeric() || chr == '_')).unwrap_or(rest.len());
+
+        if word == 0 || !rest.starts_with(|chr: char| chr.is_ascii_alphabetic()) {
+            let len = rest.chars().next().map(|chr| chr.len_utf8()).unwrap_or(1);
+            out += &rest[..len.max(word)];
+            i += len.max(word);
+            continue;
+        }
+
+        let name = &rest[..word];
+
+        if !rest[word..].starts_with('(') || !(is_generator(name) || name == "C" || functions(name)) {
+            out += name;
+            i += word;
+            continue;
+        }
+
+        let (args, close) = arguments(rest, word).ok_or(format!("no `)` after {name}("))?;
+        let args = args.split(',').map(|arg| int(arg, ints)).collect::<Result<Vec<i64>, String>>()?;
+
+        if is_generator(name) || name == "C" {
+            let args = args.iter().map(|arg| (arg + offset).to_string()).collect::<Vec<String>>();
+            out += &format!("{name}({})", args.join(", "));
+        } else {
+            let [arg] = args[..] else {
+                return Err(format!("{name} takes one index"));
+            };
+
+            let value = call(name, arg)?;
+            let var = format!("{name}__{}", arg.to_string().replace('-', "m"));
+            bound.insert(var.clone(), value);
+            out += &var;
+        }
+
+        i += close + 1;
+    }
+
+    return Ok(out);
+}
+
+impl Context<'_> {
+    fn expand(&self, src: &str, ints: &HashMap<String, i64>, bound: &mut Substitution) -> Result<String, String> {
+        let functions = |name: &str| self.clauses.contains_key(name);
+        let call = |name: &str, arg: i64| self.call(name, &self.clauses[name], arg, ints);
+        return expand(src, ints, self.offset, &functions, &call, bound);
+    }
+
+    fn call(&self, name: &str, clauses: &[(String, String)], arg: i64, ints: &HashMap<String, i64>) -> Result<Value, String> {
+        let clause = clauses
+            .iter()
+            .find(|(param, _)| param.parse() == Ok(arg))
+            .or_else(|| clauses.iter().find(|(param, _)| is_identifier(param)))
+            .ok_or(format!("{name}({arg}) is not defined"))?;
+
+        let mut ints = ints.clone();
+        ints.insert(clause.0.clone(), arg);
+
+        return self.value(&Expr::Plain(clause.1.clone()), &ints, &HashMap::new());
+    }
+
+    /// The tree of the case of `expr` that applies, with what its names are
+    /// bound to.
+    fn tree(&self, expr: &Expr, ints: &HashMap<String, i64>, terms: &Substitution) -> Result<(AST, Substitution), String> {
+        match expr {
+            Expr::Plain(src) => {
+                let mut bound = terms.clone();
+                let src = self.expand(&presets(src, terms), ints, &mut bound)?;
+                let ast = parser::parse(&src).map_err(|err| err.to_string())?;
+
+                return Ok((ast, bound));
+            }
+            Expr::If(test, then, otherwise) => {
+                let branch = if condition(test, ints)? { then } else { otherwise };
+                return self.tree(branch, ints, terms);
+            }
+        }
+    }
+
+    fn value(&self, expr: &Expr, ints: &HashMap<String, i64>, terms: &Substitution) -> Result<Value, String> {
+        let (ast, bound) = self.tree(expr, ints, terms)?;
+        return instantiate(&ast, &bound, self.cartan);
+    }
+
+    /// `element` with `terms` bound, as long as it is linear in each of
+    /// `vars`. Only then does trying them at single generators show a step
+    /// for every term.
+    fn linear_value(&self, element: &Element, ints: &HashMap<String, i64>, terms: &Substitution, vars: &[String]) -> Result<Value, Failure> {
+        let (ast, bound) = self.tree(&element.expr, ints, terms).map_err(|reason| (element.line, reason))?;
+
+        if let Some(name) = vars.iter().find(|name| !matches!(degree(&ast, name), Degree::Zero | Degree::Exactly(0 | 1))) {
+            return Err((element.line, format!("is not linear in {name}, and terms are only tried at single generators")));
+        }
+
+        return instantiate(&ast, &bound, self.cartan).map_err(|reason| (element.line, reason));
+    }
+
+    fn equal(&self, a: &Value, b: &Value) -> Result<(), String> {
+        let difference = Value::Add(Box::new(a.clone()), Box::new(Value::Negative(Box::new(b.clone()))));
+
+        match normal_form(difference, self.rules, self.cartan) {
+            Some(value) if value == Value::zero() => return Ok(()),
+            Some(value) => return Err(format!("the difference simplifies to {}", AST::from_value(&value))),
+            None => return Err("the difference does not simplify".to_string()),
+        }
+    }
+
+    /// Runs `items`, checking every step, and says where the first bad one
+    /// is and what the variables were.
+    fn run(&self, items: &[Item], ints: &mut HashMap<String, i64>, terms: &mut Substitution) -> Result<(), Unjustified> {
+        for item in items {
+            match item {
+                Item::Clause { .. } => {}
+                // What is assigned is worked out once, so everything in it
+                // has to be known already.
+                Item::Chain { target: Some(target), elements } => {
+                    let mut last: Option<(usize, Value)> = None;
+
+                    for element in elements {
+                        let fail = |reason: String| ((element.line, reason), bindings(ints, &[], terms));
+                        let value = self.value(&element.expr, ints, terms).map_err(fail)?;
+
+                        if let Some((line, last)) = &last {
+                            self.equal(last, &value).map_err(|reason| fail(format!("does not follow from line {line}: {reason}")))?;
+                        }
+
+                        last = Some((element.line, value));
+                    }
+
+                    if let Some((_, value)) = last {
+                        terms.insert(target.clone(), value);
+                    }
+                }
+                Item::Chain { target: None, elements } => {
+                    for pair in elements.windows(2) {
+                        self.step(&pair[0], &pair[1], ints, terms)?;
+                    }
+                }
+                Item::For { var, from, to, body } => {
+                    for n in *from..*to {
+                        ints.insert(var.clone(), n);
+                        self.run(body, ints, terms)?;
+                    }
+
+                    ints.remove(var);
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// The variables `element` leaves free, and whether each is an index.
+    fn free(&self, element: &Element, ints: &HashMap<String, i64>, terms: &Substitution) -> Vec<(String, bool)> {
+        let mut srcs = vec![];
+        sources(&element.expr, &mut srcs);
+
+        let mut found = vec![];
+        srcs.iter().for_each(|(src, condition)| names(&presets(src, terms), *condition, &self.clauses, &mut found));
+        found.retain(|(name, _)| !ints.contains_key(name) && !terms.contains_key(name));
+
+        // A name used as an index anywhere is one.
+        found.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+        found.dedup_by(|a, b| a.0 == b.0);
+
+        return found;
+    }
+
+    /// Checks that `b` follows from `a` for every value of the variables
+    /// they leave free: indices run over the generators of the matrix and
+    /// anything else over `E(i)`, `F(i)` and `H(i)`. That shows the step for
+    /// every term only where both sides are linear in such a variable, so a
+    /// side that is not fails.
+    ///
+    /// Where each side has variables of its own, like two ways of writing
+    /// the Jacobi identity, the sides are equal everywhere if they are with
+    /// the other side's own variables held at one value, which saves
+    /// running through both sets at once.
+    fn step(&self, a: &Element, b: &Element, ints: &HashMap<String, i64>, terms: &Substitution) -> Result<(), Unjustified> {
+        let (of_a, of_b) = (self.free(a, ints, terms), self.free(b, ints, terms));
+
+        let rank = self.cartan.rank() as i64;
+        let range = (1 - self.offset..=rank - self.offset).collect::<Vec<i64>>();
+        let generators = (1..=rank as i32).flat_map(|i| [e(i), f(i), h(i)]).collect::<Vec<Value>>();
+
+        let mut passes = vec![(&of_a, &of_b)];
+
+        if of_b.iter().any(|name| !of_a.contains(name)) {
+            passes.push((&of_b, &of_a));
+        }
+
+        for (own, other) in passes {
+            let held = other.iter().filter(|name| !own.contains(name)).collect::<Vec<&(String, bool)>>();
+            let (mut ints, mut terms) = (ints.clone(), terms.clone());
+
+            for (name, index) in &held {
+                if *index {
+                    ints.insert(name.clone(), range[0]);
+                } else {
+                    terms.insert(name.clone(), generators[0].clone());
+                }
+            }
+
+            let indices = own.iter().filter(|(_, index)| *index).map(|(name, _)| name.clone()).collect::<Vec<String>>();
+            let enumerated = own.iter().filter(|(_, index)| !*index).map(|(name, _)| name.clone()).collect::<Vec<String>>();
+            let vars = own.iter().chain(held).filter(|(_, index)| !*index).map(|(name, _)| name.clone()).collect::<Vec<String>>();
+
+            let mut failure = None;
+
+            each(&indices, &range, &mut ints, &mut |ints| {
+                return each(&enumerated, &generators, &mut terms.clone(), &mut |terms| {
+                    let checked = self
+                        .linear_value(a, ints, terms, &vars)
+                        .and_then(|x| Ok((x, self.linear_value(b, ints, terms, &vars)?)))
+                        .and_then(|(x, y)| self.equal(&x, &y).map_err(|reason| (b.line, format!("does not follow from line {}: {reason}", a.line))));
+
+                    if let Err(at) = checked {
+                        failure = Some((at, bindings(ints, &vars, terms)));
+                        return false;
+                    }
+
+                    return true;
+                });
+            });
+
+            if let Some(failure) = failure {
+                return Err(failure);
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+/// How a side of a step depends on a term variable.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Degree {
+    /// Zero, which has every degree.
+    Zero,
+    /// Every term has the variable this many times.
+    Exactly(usize),
+    /// Terms have it different numbers of times.
+    Mixed,
+}
+
+fn degree(ast: &AST, name: &str) -> Degree {
+    match ast {
+        AST::Named(var) => return Degree::Exactly(if var == name { 1 } else { 0 }),
+        AST::Value(Value::Number(n)) if n.is_zero() => return Degree::Zero,
+        AST::Value(_) | AST::Kind(_, _) | AST::C(_, _) => return Degree::Exactly(0),
+        AST::Negative(a) => return degree(a, name),
+        AST::Add(a, b) => match (degree(a, name), degree(b, name)) {
+            (Degree::Zero, d) | (d, Degree::Zero) => return d,
+            (Degree::Exactly(m), Degree::Exactly(n)) if m == n => return Degree::Exactly(m),
+            _ => return Degree::Mixed,
+        },
+        AST::Braket(a, b) | AST::Mul(a, b) => match (degree(a, name), degree(b, name)) {
+            (Degree::Zero, _) | (_, Degree::Zero) => return Degree::Zero,
+            (Degree::Exactly(m), Degree::Exactly(n)) => return Degree::Exactly(m + n),
+            _ => return Degree::Mixed,
+        },
+    }
+}
+
+/// A step that does not follow, and the values of the variables it fails
+/// for.
+type Unjustified = (Failure, Vec<(String, String)>);
+
+fn bindings(ints: &HashMap<String, i64>, vars: &[String], terms: &Substitution) -> Vec<(String, String)> {
+    let mut out = ints.iter().map(|(name, n)| (name.clone(), n.to_string())).collect::<Vec<(String, String)>>();
+    out.sort();
+    out.extend(vars.iter().map(|name| (name.clone(), AST::from_value(&terms[name]).to_string())));
+
+    return out;
+}
+
+/// Calls `k` with `bound` and every way of giving each name one of
+/// `values`, until `k` returns false. Returns whether it never did.
+fn each<T: Clone>(names: &[String], values: &[T], bound: &mut HashMap<String, T>, k: &mut dyn FnMut(&HashMap<String, T>) -> bool) -> bool {
+    let Some((name, rest)) = names.split_first() else {
+        return k(bound);
+    };
+
+    for value in values {
+        bound.insert(name.clone(), value.clone());
+
+        if !each(rest, values, bound, k) {
+            return false;
+        }
+    }
+
+    return true;
+}
+
+///////
+
+fn sources<'a>(expr: &'a Expr, out: &mut Vec<(&'a str, bool)>) {
+    match expr {
+        Expr::Plain(src) => out.push((src, false)),
+        Expr::If(test, then, otherwise) => {
+            out.push((test, true));
+            sources(then, out);
+            sources(otherwise, out);
+        }
+    }
+}
+
+/// `src` with the names `n_f` uses written out, where nothing else has
+/// been assigned to them.
+fn presets(src: &str, terms: &Substitution) -> String {
+    let mut out = String::new();
+    let mut rest = src;
+
+    while let Some(start) = rest.find(|chr: char| chr.is_ascii_alphabetic()) {
+        let len = rest[start..].find(|chr: char| !(chr.is_ascii_alphanumeric() || chr == '_')).unwrap_or(rest.len() - start);
+        let name = &rest[start..start + len];
+
+        out += &rest[..start];
+
+        match PRESETS.iter().find(|(preset, _)| *preset == name && !terms.contains_key(name)) {
+            Some((_, written)) => out += &format!("({written})"),
+            None => out += name,
+        }
+
+        rest = &rest[start + len..];
+    }
+
+    return out + rest;
+}
+
+/// The names in `src` that are not keywords or called, and whether each is
+/// an index: in a condition, or an argument of a generator, `C` or a
+/// function.
+fn names(src: &str, condition: bool, functions: &HashMap<String, Vec<(String, String)>>, out: &mut Vec<(String, bool)>) {
+    let mut stack = vec![condition];
+    let tokens = tokens(src);
+
+    for (i, token) in tokens.iter().enumerate() {
+        let called = tokens.get(i + 1).map(|next| next.as_str()) == Some("(");
+
+        match token.as_str() {
+            "(" => {
+                let name = if i > 0 { tokens[i - 1].as_str() } else { "" };
+                let index = is_generator(name) || name == "C" || functions.contains_key(name);
+                stack.push(index || *stack.last().unwrap_or(&false));
+            }
+            ")" => {
+                stack.pop();
+            }
+            _ if called || !is_identifier(token) || KEYWORDS.contains(&token.as_str()) => {}
+            _ => out.push((token.clone(), *stack.last().unwrap_or(&false))),
+        }
+    }
+}
+
+fn count(items: &[Item]) -> usize {
+    return items
+        .iter()
+        .map(|item| match item {
+            Item::Clause { .. } => 0,
+            Item::Chain { elements, .. } => elements.len().saturating_sub(1),
+            Item::For { body, .. } => count(body),
+        })
+        .sum();
+}
+
+/// Checks every step of the derivations in `src`: each expression of a
+/// chain has to simplify to the one before it under `rules`. Chains are
+/// consecutive lines, or expressions with a `v` line between them, and
+/// blocks are separated by `...` lines.
+///
+/// Returns the first step that does not follow, or how many steps there
+/// are.
+pub fn verify(src: &str, rules: &Vec<Rule>, cartan: &Arc<CartanMatrix>) -> Result<usize, Unjustified> {
+    let mut context = Context { cartan, rules, clauses: HashMap::new(), offset: offset(src) };
+
+    let lines = src
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim().trim_end_matches(';').trim_end().to_string()))
+        .collect::<Vec<(usize, String)>>();
+
+    let mut steps = 0;
+
+    for block in lines.split(|(_, line)| line == "...") {
+        let mut lines = Lines { lines: block.to_vec(), i: 0 };
+        let items = lines.items(false).map_err(|failure| (failure, vec![]))?;
+
+        if let Some((number, line)) = lines.peek() {
+            return Err(((*number, format!("unexpected `{line}`")), vec![]));
+        }
+
+        for item in &items {
+            if let Item::Clause { name, param, body } = item {
+                context.clauses.entry(name.clone()).or_default().push((param.clone(), body.clone()));
+            }
+        }
+
+        steps += count(&items);
+        context.run(&items, &mut HashMap::new(), &mut HashMap::new())?;
+    }
+
+    return Ok(steps);
+}
+
+/// Verifies the derivations in the file at `path`, which hold if every
+/// step follows.
+pub fn report(path: &str, rules: &RuleSet, cartan: &Arc<CartanMatrix>) -> Result<Verdict, ProofError> {
+    let src = std::fs::read_to_string(path).map_err(|source| ProofError::Io { path: path.to_string(), source })?;
+
+    match verify(&src, rules, cartan) {
+        Ok(steps) => return Ok(Verdict { holds: true, text: format!("{path}: {steps} steps follow\n") }),
+        Err(((line, reason), with)) => {
+            let mut text = format!("{path}:{line}: {reason}\n");
+
+            if !with.is_empty() {
+                let with = with.iter().map(|(name, value)| format!("{name} = {value}")).collect::<Vec<String>>();
+                text += &format!("    with {}\n", with.join(", "));
+            }
+
+            return Ok(Verdict { holds: false, text });
+        }
+    }
+}
+
+///////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (RuleSet, Arc<CartanMatrix>) {
+        let cartan = Arc::new(CartanMatrix::load("./src/matrix").unwrap());
+        let rules = RuleSet::load("./src/map", &cartan).unwrap();
+
+        return (rules, cartan);
+    }
+
+    #[test]
+    fn the_derivations_in_docs_verify() {
+        let (rules, cartan) = setup();
+
+        for path in ["docs/derivations", "docs/nx_f0", "docs/nx_h0"] {
+            let src = std::fs::read_to_string(path).unwrap();
+            let steps = verify(&src, &rules, &cartan);
+
+            assert!(steps.as_ref().is_ok_and(|steps| *steps > 0), "{path}: {steps:?}");
+        }
+    }
+
+    #[test]
+    fn a_sign_flipped_step_fails_on_its_line() {
+        let (rules, cartan) = setup();
+        let src = std::fs::read_to_string("docs/nx_h0").unwrap();
+
+        // Line 20 expands [[N(n - 1), E(n % 3)], H(0)] by Jacobi.
+        let flipped = src
+            .lines()
+            .enumerate()
+            .map(|(i, line)| if i + 1 == 20 { line.replace("] - [[E(n % 3)", "] + [[E(n % 3)") } else { line.to_string() })
+            .collect::<Vec<String>>()
+            .join("\n");
+        assert_ne!(flipped, src);
+
+        let ((line, _), _) = verify(&flipped, &rules, &cartan).unwrap_err();
+        assert_eq!(line, 20);
+    }
+}
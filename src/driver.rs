@@ -0,0 +1,273 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::cartan::CartanMatrix;
+use crate::checkpoint::{self, Checkpoint};
+use crate::error::ProofError;
+use crate::ring::{Coefficients, Integers, Rationals, Ring};
+use crate::search::{self, Progress, Report, Search, Visitor, Walk};
+use crate::value::*;
+use crate::{find_conter_exmaple, normal_form, RuleSet, Scope, Verdict};
+
+/// How to run a search, apart from what to search.
+pub struct SearchOptions {
+    /// Threads to spread an enumeration over, all cores for 0.
+    pub threads: usize,
+    /// Where to save the search, and how often.
+    pub checkpoint: Option<(String, Duration)>,
+    /// The longest word to look at, the only end a sequence has.
+    pub max_depth: Option<usize>,
+}
+
+/// Writes what a search finds to `out`, saving a checkpoint every so often.
+struct Driver<'a> {
+    search: &'a Search,
+    cartan: &'a CartanMatrix,
+    coefficients: Coefficients,
+    checked: u64,
+    found: u64,
+    checkpoint: Option<(String, Duration)>,
+    saved: Instant,
+    /// The longest word to look at, the only end a sequence has.
+    max_depth: Option<usize>,
+    out: &'a mut dyn FnMut(String),
+}
+
+impl<R: Ring> Visitor<R> for Driver<'_> {
+    fn visit(&mut self, report: &Report) -> bool {
+        if self.max_depth.is_some_and(|max| report.word.len() > max) {
+            return !matches!(self.search, Search::Periodic(_));
+        }
+
+        self.checked += 1;
+
+        if self.checked.is_multiple_of(500) {
+            (self.out)(format!("Checking {}", self.checked));
+        }
+
+        match self.search {
+            Search::Word(_) => (self.out)(report.to_string()),
+            // A single sequence stops at the first word that vanishes.
+            Search::Periodic(_) => {
+                if report.vanishes {
+                    (self.out)(report.to_string());
+                    self.found += report.is_candidate() as u64;
+                    return false;
+                }
+            }
+            Search::Words(_) | Search::BreadthFirst(_) => {
+                if report.is_candidate() {
+                    (self.out)(report.to_string());
+                    self.found += 1;
+                }
+            }
+        }
+
+        return true;
+    }
+
+    fn progress(&mut self, progress: impl FnOnce() -> Progress<R>) {
+        let Some((path, every)) = &self.checkpoint else {
+            return;
+        };
+
+        if self.saved.elapsed() < *every {
+            return;
+        }
+
+        let checkpoint = Checkpoint {
+            cartan: self.cartan.clone(),
+            coefficients: self.coefficients,
+            search: self.search.clone(),
+            checked: self.checked,
+            found: self.found,
+            max_depth: self.max_depth,
+            progress: progress(),
+        };
+
+        if let Err(err) = checkpoint.save(path) {
+            eprintln!("Could not save checkpoint {path}: {err}");
+        }
+
+        self.saved = Instant::now();
+    }
+}
+
+impl Driver<'_> {
+    /// Runs the search, returning how many candidates it found in all.
+    fn run<R: Ring>(&mut self, ring: &R, threads: usize, progress: Option<Progress<R>>) -> Result<u64, ProofError> {
+        self.search.run(self.cartan, ring, threads, progress, self)?;
+
+        (self.out)(format!("Checked {} words, {} candidates", self.checked, self.found));
+
+        return Ok(self.found);
+    }
+}
+
+fn search_over<R: Ring>(words: &Search, cartan: &CartanMatrix, coefficients: Coefficients, ring: &R, options: &SearchOptions, out: &mut dyn FnMut(String)) -> Result<u64, ProofError> {
+    let mut driver = Driver {
+        search: words,
+        cartan,
+        coefficients,
+        checked: 0,
+        found: 0,
+        checkpoint: options.checkpoint.clone(),
+        saved: Instant::now(),
+        max_depth: options.max_depth,
+        out,
+    };
+
+    return driver.run(ring, options.threads, None);
+}
+
+/// Searches `words` with `Walk`, giving each line of what it finds to `out`.
+/// Returns how many candidates it found.
+///
+/// Over `Modular` coefficients a zero found here is only zero mod `p`, so
+/// it is worth confirming over the integers before trusting it.
+pub fn search(words: &Search, cartan: &CartanMatrix, coefficients: Coefficients, options: &SearchOptions, out: &mut dyn FnMut(String)) -> Result<u64, ProofError> {
+    match coefficients {
+        Coefficients::Integers => return search_over(words, cartan, coefficients, &Integers, options, out),
+        Coefficients::Rationals => return search_over(words, cartan, coefficients, &Rationals, options, out),
+        Coefficients::Modular(ring) => return search_over(words, cartan, coefficients, &ring, options, out),
+    }
+}
+
+fn resume_over<R: Ring>(path: &str, src: &str, ring: &R, options: &SearchOptions, out: &mut dyn FnMut(String)) -> Result<u64, ProofError> {
+    let Checkpoint { cartan, coefficients, search, checked, found, max_depth, progress } = Checkpoint::read(src, ring)
+        .ok_or(ProofError::Checkpoint { path: path.to_string() })?;
+
+    // Words already checked were cut off at the saved depth.
+    if let Some(given) = options.max_depth.filter(|given| max_depth != Some(*given)) {
+        return Err(ProofError::ResumeDepth { path: path.to_string(), saved: max_depth, given });
+    }
+
+    out(cartan.report().trim_end().to_string());
+
+    let mut driver = Driver {
+        search: &search,
+        cartan: &cartan,
+        coefficients,
+        checked,
+        found,
+        checkpoint: options.checkpoint.clone(),
+        saved: Instant::now(),
+        max_depth,
+        out,
+    };
+
+    return driver.run(ring, options.threads, Some(progress));
+}
+
+/// Goes on with the search saved at `path`, which carries its own matrix,
+/// coefficients, words and max depth, giving each line of what it finds to
+/// `out`. Returns how many candidates it found in all.
+pub fn resume(path: &str, options: &SearchOptions, out: &mut dyn FnMut(String)) -> Result<u64, ProofError> {
+    let src = std::fs::read_to_string(path).map_err(|source| ProofError::Io { path: path.to_string(), source })?;
+    let coefficients = checkpoint::coefficients(&src).ok_or(ProofError::Checkpoint { path: path.to_string() })?;
+
+    match coefficients {
+        Coefficients::Integers => return resume_over(path, &src, &Integers, options, out),
+        Coefficients::Rationals => return resume_over(path, &src, &Rationals, options, out),
+        Coefficients::Modular(ring) => return resume_over(path, &src, &ring, options, out),
+    }
+}
+
+/// What `Walk` would report about `word`, worked out with the rewrite rules
+/// instead.
+pub fn rules_report(word: &[i32], rules: &RuleSet, cartan: &Arc<CartanMatrix>) -> Result<Report, ProofError> {
+    let (first, rest) = word.split_first().ok_or(ProofError::EmptyWord)?;
+    let nx = rest.iter().fold(e(*first), |nx, n| brak(nx, e(*n)));
+    let zero = normal_form(nx, rules, &Scope::new(cartan))? == Value::zero();
+
+    return Ok(Report { word: word.to_vec(), zero, vanishes: find_conter_exmaple(word, rules, cartan)? });
+}
+
+/// `search` with the rewrite rules in place of `Walk`, over the integers
+/// and on one thread. Much slower, but a check on it. Words are taken in the
+/// same order as `Walk` takes them, and a sequence goes on up to `max_depth`
+/// letters. The rules and `Walk` do not see the same zeros, so the two can
+/// check different numbers of words.
+pub fn search_with_rules(words: &Search, rules: &RuleSet, cartan: &Arc<CartanMatrix>, max_depth: usize, out: &mut dyn FnMut(String)) -> Result<u64, ProofError> {
+    let rank = cartan.rank() as i32;
+    let (mut checked, mut found) = (0, 0);
+
+    // Whether to go on past `word`. As with `Walk`, words are extended until
+    // `N` is zero, and a single sequence stops at the first that vanishes.
+    let periodic = matches!(words, Search::Periodic(_));
+    let mut visit = |word: &[i32], print_all: bool| -> Result<bool, ProofError> {
+        let report = rules_report(word, rules, cartan)?;
+        checked += 1;
+
+        if print_all || report.is_candidate() || (report.vanishes && periodic) {
+            out(report.to_string());
+        }
+
+        found += report.is_candidate() as u64;
+        return Ok(!(report.zero || report.vanishes && periodic));
+    };
+
+    match words {
+        Search::Word(word) => {
+            visit(word, true)?;
+        }
+        Search::Periodic(pattern) => {
+            for length in 1..=max_depth {
+                let word = pattern.iter().cycle().take(length).copied().collect::<Vec<i32>>();
+
+                if !visit(&word, false)? {
+                    break;
+                }
+            }
+        }
+        Search::Words(length) => {
+            let mut stack = (1..=rank).rev().map(|e| vec![e]).collect::<Vec<Vec<i32>>>();
+
+            while let Some(word) = stack.pop() {
+                if visit(&word, false)? && word.len() < (*length).min(max_depth) {
+                    stack.extend((1..=rank).rev().map(|e| [word.clone(), vec![e]].concat()));
+                }
+            }
+        }
+        Search::BreadthFirst(length) => {
+            let mut queue = (1..=rank).map(|e| vec![e]).collect::<VecDeque<Vec<i32>>>();
+
+            while let Some(word) = queue.pop_front() {
+                if visit(&word, false)? && word.len() < (*length).min(max_depth) {
+                    queue.extend((1..=rank).map(|e| [word.clone(), vec![e]].concat()));
+                }
+            }
+        }
+    }
+
+    out(format!("Checked {checked} words, {found} candidates"));
+
+    return Ok(found);
+}
+
+/// Times `Walk` and the rewrite rules on every prefix of the cycle through
+/// the generators. The verdict holds if they agree.
+pub fn bench(length: usize, rules: &RuleSet, cartan: &Arc<CartanMatrix>) -> Result<Verdict, ProofError> {
+    let word = (1..=cartan.rank() as i32).cycle().take(length).collect::<Vec<i32>>();
+    let prefixes = (1..=length).map(|n| &word[..n]).collect::<Vec<&[i32]>>();
+
+    let start = Instant::now();
+    let walks = prefixes.iter().map(|word| Ok(Walk::along(word, cartan, &Integers)?.report())).collect::<Result<Vec<Report>, ProofError>>()?;
+    let walk = start.elapsed();
+
+    let start = Instant::now();
+    let reports = prefixes.iter().map(|word| rules_report(word, rules, cartan)).collect::<Result<Vec<Report>, ProofError>>()?;
+    let rewriting = start.elapsed();
+
+    let mut text = format!("walk:  {walk:?} for {length} words\nrules: {rewriting:?} for {length} words\n");
+
+    for (walk, rules) in walks.iter().zip(&reports) {
+        if walk.vanishes != rules.vanishes {
+            text += &format!("They disagree on {}: {walk} against {rules}\n", search::show_word(&walk.word));
+            return Ok(Verdict { holds: false, text });
+        }
+    }
+
+    return Ok(Verdict { holds: true, text });
+}
@@ -0,0 +1,93 @@
+use std::fmt::Display;
+
+use crate::parser::ParseError;
+
+/// Why input given to the crate could not be used.
+#[derive(Debug)]
+pub enum ProofError {
+    /// A file that could not be read or written, and why.
+    Io { path: String, source: std::io::Error },
+    /// A rule file or expression that does not parse, with every bad line.
+    Parse(Vec<ParseError>),
+    /// A matrix that is not square, or has an entry that is not an integer.
+    Cartan(String),
+    /// A checkpoint that is not one a search saved.
+    Checkpoint { path: String },
+    /// A saved search resumed with another max depth than it was saved with.
+    ResumeDepth { path: String, saved: Option<usize>, given: usize },
+    /// Rewriting that ran out of steps, went too deep or looped, and why.
+    Rewrite(String),
+    /// A variable used in an expression or a rule's right-hand side that
+    /// nothing binds.
+    Unbound(String),
+    /// A variable or term that is not what its place needs, such as a
+    /// generator index bound to a term.
+    Mismatch { what: String, expected: &'static str, found: String },
+    /// A rule with `C(a, b)` in its pattern, which matching cannot undo.
+    CInPattern(String),
+    /// A generator index outside `1..=rank` of the Cartan matrix.
+    OutOfRange { index: i32, rank: usize },
+    /// A product neither side of which is a number, where collecting terms
+    /// needs a coefficient.
+    NonNumeric(String),
+    /// A word with no letters, where a search or `[N, F(b)]` needs one.
+    EmptyWord,
+    /// A coefficient that is not an integer, where a term is written with
+    /// integer coefficients.
+    NotInteger(String),
+    /// A sum of brackets whose expansion has no Lyndon basis coordinates,
+    /// which only a ring where the expansion goes wrong can give.
+    NotLie,
+    /// A trace step at a position its term does not have.
+    NoSubterm { position: String, term: String },
+    /// A section asked for that has no rules in the rule file.
+    NoSection { path: String, section: String },
+}
+
+impl Display for ProofError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProofError::Io { path, .. } => write!(f, "Could not open {path}"),
+            ProofError::Parse(errors) => {
+                let errors = errors.iter().map(|err| err.to_string()).collect::<Vec<String>>();
+                write!(f, "{}", errors.join("\n"))
+            }
+            ProofError::Cartan(reason) => write!(f, "{reason}"),
+            ProofError::Checkpoint { path } => write!(f, "Checkpoint {path} is corrupt"),
+            ProofError::ResumeDepth { path, saved: Some(saved), given } => write!(f, "Checkpoint {path} has max depth {saved}, not {given}"),
+            ProofError::ResumeDepth { path, saved: None, given } => write!(f, "Checkpoint {path} has no max depth, not {given}"),
+            ProofError::Rewrite(reason) => write!(f, "{reason}"),
+            ProofError::Unbound(name) => write!(f, "{name} is not bound"),
+            ProofError::Mismatch { what, expected, found } => write!(f, "Expected {expected} for {what}, got {found}"),
+            ProofError::CInPattern(rule) => write!(f, "C(a, b) cannot be matched, in the left-hand side of {rule}"),
+            ProofError::OutOfRange { index, rank } => write!(f, "Index {index} is out of range for a rank {rank} Cartan matrix"),
+            ProofError::NonNumeric(product) => write!(f, "{product} has no number for a coefficient"),
+            ProofError::EmptyWord => write!(f, "A word needs at least one letter"),
+            ProofError::NotInteger(n) => write!(f, "Coefficient {n} is not an integer"),
+            ProofError::NoSubterm { position, term } => write!(f, "No subterm at {position} of {term}"),
+            ProofError::NoSection { path, section } => write!(f, "No rules in section {section} of {path}"),
+            ProofError::NotLie => write!(f, "A sum of brackets expanded to a polynomial that is not a Lie element"),
+        }
+    }
+}
+
+impl std::error::Error for ProofError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProofError::Io { source, .. } => return Some(source),
+            _ => return None,
+        }
+    }
+}
+
+impl From<ParseError> for ProofError {
+    fn from(err: ParseError) -> ProofError {
+        return ProofError::Parse(vec![err]);
+    }
+}
+
+impl From<Vec<ParseError>> for ProofError {
+    fn from(errors: Vec<ParseError>) -> ProofError {
+        return ProofError::Parse(errors);
+    }
+}
@@ -0,0 +1,436 @@
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub};
+use std::str::FromStr;
+
+/// An arbitrary precision integer.
+///
+/// Values that fit in an `i64` are stored inline so the common case stays as
+/// cheap as machine arithmetic; anything larger spills into a sign and a
+/// little endian list of base `2^32` limbs.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Integer(Repr);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Repr {
+    Small(i64),
+    // Never holds a value that fits in an `i64`, so equality stays structural.
+    Big { negative: bool, limbs: Vec<u32> },
+}
+
+impl Integer {
+    pub fn zero() -> Integer {
+        return Integer(Repr::Small(0));
+    }
+
+    pub fn one() -> Integer {
+        return Integer(Repr::Small(1));
+    }
+
+    pub fn is_zero(&self) -> bool {
+        return self.0 == Repr::Small(0);
+    }
+
+    pub fn is_one(&self) -> bool {
+        return self.0 == Repr::Small(1);
+    }
+
+    pub fn is_negative(&self) -> bool {
+        match &self.0 {
+            Repr::Small(n) => *n < 0,
+            Repr::Big { negative, .. } => *negative,
+        }
+    }
+
+    pub fn abs(&self) -> Integer {
+        return if self.is_negative() { -self.clone() } else { self.clone() };
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        match &self.0 {
+            Repr::Small(n) => Some(*n),
+            Repr::Big { .. } => None,
+        }
+    }
+
+    pub fn to_i32(&self) -> Option<i32> {
+        return self.to_i64()?.try_into().ok();
+    }
+
+    /// Truncating division, so the remainder takes the sign of `self`.
+    pub fn div_rem(&self, other: &Integer) -> (Integer, Integer) {
+        if other.is_zero() {
+            panic!("Division of {self} by zero!");
+        }
+
+        if let (Repr::Small(a), Repr::Small(b)) = (&self.0, &other.0) {
+            if let (Some(q), Some(r)) = (a.checked_div(*b), a.checked_rem(*b)) {
+                return (Integer(Repr::Small(q)), Integer(Repr::Small(r)));
+            }
+        }
+
+        let (a_negative, a) = self.parts();
+        let (b_negative, b) = other.parts();
+        let (q, r) = mag_div_rem(&a, &b);
+
+        return (Integer::from_parts(a_negative != b_negative, q), Integer::from_parts(a_negative, r));
+    }
+
+    pub fn gcd(&self, other: &Integer) -> Integer {
+        let mut a = self.abs();
+        let mut b = other.abs();
+
+        while !b.is_zero() {
+            let r = a.div_rem(&b).1;
+            a = b;
+            b = r;
+        }
+
+        return a;
+    }
+
+    fn parts(&self) -> (bool, Vec<u32>) {
+        match &self.0 {
+            Repr::Small(n) => {
+                let m = n.unsigned_abs();
+                (*n < 0, trim(vec![m as u32, (m >> 32) as u32]))
+            }
+            Repr::Big { negative, limbs } => (*negative, limbs.clone()),
+        }
+    }
+
+    fn from_parts(negative: bool, limbs: Vec<u32>) -> Integer {
+        let limbs = trim(limbs);
+
+        if limbs.len() <= 2 {
+            let m = limbs.iter().rev().fold(0u64, |m, limb| (m << 32) | *limb as u64);
+
+            if negative && m <= i64::MAX as u64 + 1 {
+                return Integer(Repr::Small((m as i64).wrapping_neg()));
+            }
+
+            if !negative && m <= i64::MAX as u64 {
+                return Integer(Repr::Small(m as i64));
+            }
+        }
+
+        return Integer(Repr::Big { negative, limbs });
+    }
+}
+
+fn trim(mut limbs: Vec<u32>) -> Vec<u32> {
+    while limbs.last() == Some(&0) {
+        limbs.pop();
+    }
+
+    return limbs;
+}
+
+fn mag_cmp(a: &[u32], b: &[u32]) -> Ordering {
+    return a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()));
+}
+
+fn mag_add(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0u64;
+
+    for i in 0..a.len().max(b.len()) {
+        let sum = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        out.push(sum as u32);
+        carry = sum >> 32;
+    }
+
+    out.push(carry as u32);
+
+    return trim(out);
+}
+
+/// `a - b` for `a >= b`.
+fn mag_sub(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+
+    for (i, x) in a.iter().enumerate() {
+        let mut diff = *x as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+
+        if diff < 0 {
+            diff += 1 << 32;
+            borrow = 1;
+        }
+
+        out.push(diff as u32);
+    }
+
+    return trim(out);
+}
+
+fn mag_mul(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = vec![0u32; a.len() + b.len()];
+
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+
+        for (j, y) in b.iter().enumerate() {
+            let cur = out[i + j] as u64 + *x as u64 * *y as u64 + carry;
+            out[i + j] = cur as u32;
+            carry = cur >> 32;
+        }
+
+        out[i + b.len()] = carry as u32;
+    }
+
+    return trim(out);
+}
+
+fn mag_div_rem_small(a: &[u32], d: u32) -> (Vec<u32>, u32) {
+    let mut out = vec![0u32; a.len()];
+    let mut rem = 0u64;
+
+    for i in (0..a.len()).rev() {
+        let cur = (rem << 32) | a[i] as u64;
+        out[i] = (cur / d as u64) as u32;
+        rem = cur % d as u64;
+    }
+
+    return (trim(out), rem as u32);
+}
+
+/// Shift and subtract long division, one bit at a time.
+fn mag_div_rem(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if b.len() == 1 {
+        let (q, r) = mag_div_rem_small(a, b[0]);
+        return (q, trim(vec![r]));
+    }
+
+    let mut q = vec![0u32; a.len()];
+    let mut r: Vec<u32> = vec![];
+
+    for bit in (0..a.len() * 32).rev() {
+        // r = r * 2 + next bit of a
+        let mut carry = (a[bit / 32] >> (bit % 32)) & 1;
+        for limb in r.iter_mut() {
+            let next = *limb >> 31;
+            *limb = (*limb << 1) | carry;
+            carry = next;
+        }
+        if carry != 0 {
+            r.push(carry);
+        }
+
+        if mag_cmp(&r, b) != Ordering::Less {
+            r = mag_sub(&r, b);
+            q[bit / 32] |= 1 << (bit % 32);
+        }
+    }
+
+    return (trim(q), trim(r));
+}
+
+impl From<i64> for Integer {
+    fn from(n: i64) -> Self {
+        return Integer(Repr::Small(n));
+    }
+}
+
+impl From<i32> for Integer {
+    fn from(n: i32) -> Self {
+        return Integer(Repr::Small(n as i64));
+    }
+}
+
+impl Neg for Integer {
+    type Output = Integer;
+
+    fn neg(self) -> Integer {
+        match self.0 {
+            Repr::Small(n) => match n.checked_neg() {
+                Some(n) => Integer(Repr::Small(n)),
+                None => Integer::from_parts(false, vec![0, 1 << 31]),
+            },
+            Repr::Big { negative, limbs } => Integer::from_parts(!negative, limbs),
+        }
+    }
+}
+
+impl Add for Integer {
+    type Output = Integer;
+
+    fn add(self, other: Integer) -> Integer {
+        if let (Repr::Small(a), Repr::Small(b)) = (&self.0, &other.0) {
+            if let Some(n) = a.checked_add(*b) {
+                return Integer(Repr::Small(n));
+            }
+        }
+
+        let (a_negative, a) = self.parts();
+        let (b_negative, b) = other.parts();
+
+        if a_negative == b_negative {
+            return Integer::from_parts(a_negative, mag_add(&a, &b));
+        }
+
+        return match mag_cmp(&a, &b) {
+            Ordering::Less => Integer::from_parts(b_negative, mag_sub(&b, &a)),
+            _ => Integer::from_parts(a_negative, mag_sub(&a, &b)),
+        };
+    }
+}
+
+impl Sub for Integer {
+    type Output = Integer;
+
+    fn sub(self, other: Integer) -> Integer {
+        return self + -other;
+    }
+}
+
+impl Mul for Integer {
+    type Output = Integer;
+
+    fn mul(self, other: Integer) -> Integer {
+        if let (Repr::Small(a), Repr::Small(b)) = (&self.0, &other.0) {
+            if let Some(n) = a.checked_mul(*b) {
+                return Integer(Repr::Small(n));
+            }
+        }
+
+        let (a_negative, a) = self.parts();
+        let (b_negative, b) = other.parts();
+
+        return Integer::from_parts(a_negative != b_negative, mag_mul(&a, &b));
+    }
+}
+
+impl Ord for Integer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if let (Repr::Small(a), Repr::Small(b)) = (&self.0, &other.0) {
+            return a.cmp(b);
+        }
+
+        let (a_negative, a) = self.parts();
+        let (b_negative, b) = other.parts();
+
+        return match (a_negative, b_negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => mag_cmp(&a, &b),
+            (true, true) => mag_cmp(&b, &a),
+        };
+    }
+}
+
+impl PartialOrd for Integer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+impl Display for Integer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (negative, mut limbs) = match &self.0 {
+            Repr::Small(n) => return write!(f, "{n}"),
+            Repr::Big { negative, limbs } => (*negative, limbs.clone()),
+        };
+
+        // Peel off nine decimal digits at a time.
+        let mut chunks = vec![];
+
+        while !limbs.is_empty() {
+            let (q, r) = mag_div_rem_small(&limbs, 1_000_000_000);
+            chunks.push(r);
+            limbs = q;
+        }
+
+        if negative {
+            write!(f, "-")?;
+        }
+
+        write!(f, "{}", chunks.pop().unwrap_or(0))?;
+
+        for chunk in chunks.iter().rev() {
+            write!(f, "{chunk:09}")?;
+        }
+
+        return Ok(());
+    }
+}
+
+impl FromStr for Integer {
+    type Err = ();
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        if let Ok(n) = src.parse::<i64>() {
+            return Ok(Integer(Repr::Small(n)));
+        }
+
+        let (negative, digits) = match src.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, src),
+        };
+
+        if digits.is_empty() || !digits.chars().all(|chr| chr.is_ascii_digit()) {
+            return Err(());
+        }
+
+        let mut limbs = vec![];
+
+        for digit in digits.bytes() {
+            limbs = mag_add(&mag_mul(&limbs, &[10]), &[(digit - b'0') as u32]);
+        }
+
+        return Ok(Integer::from_parts(negative, limbs));
+    }
+}
+
+impl AddAssign for Integer {
+    fn add_assign(&mut self, other: Integer) {
+        *self = std::mem::replace(self, Integer::zero()) + other;
+    }
+}
+
+impl MulAssign for Integer {
+    fn mul_assign(&mut self, other: Integer) {
+        *self = std::mem::replace(self, Integer::zero()) * other;
+    }
+}
+
+///////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn int(src: &str) -> Integer {
+        return src.parse().unwrap_or_else(|_| panic!("{src} is not an integer"));
+    }
+
+    #[test]
+    fn sums_carry_into_new_limbs() {
+        assert_eq!(Integer::from(i64::MAX) + Integer::one(), int("9223372036854775808"));
+        assert_eq!(int("18446744073709551615") + Integer::one(), int("18446744073709551616"));
+        assert_eq!(int("18446744073709551616") * int("18446744073709551616"), int("340282366920938463463374607431768211456"));
+        assert_eq!(int("-18446744073709551616") + int("18446744073709551617"), Integer::one());
+    }
+
+    #[test]
+    fn division_truncates_towards_zero() {
+        let two_64 = int("18446744073709551616");
+
+        assert_eq!(int("340282366920938463463374607431768211461").div_rem(&two_64), (two_64.clone(), Integer::from(5)));
+        assert_eq!(int("-340282366920938463463374607431768211461").div_rem(&two_64), (-two_64.clone(), Integer::from(-5)));
+        assert_eq!(Integer::from(-7).div_rem(&Integer::from(2)), (Integer::from(-3), Integer::from(-1)));
+        assert_eq!(Integer::from(i64::MIN).div_rem(&Integer::from(-1)), (int("9223372036854775808"), Integer::zero()));
+    }
+
+    #[test]
+    fn min_negates_out_of_a_machine_word_and_back() {
+        let min = Integer::from(i64::MIN);
+
+        assert_eq!(-min.clone(), int("9223372036854775808"));
+        assert_eq!(-(-min.clone()), min);
+        assert_eq!((-min.clone()).to_i64(), None);
+        assert_eq!(min.abs().to_string(), "9223372036854775808");
+        assert_eq!((min.clone() - Integer::one()) + Integer::one(), min);
+    }
+}
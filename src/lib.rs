@@ -0,0 +1,960 @@
+#![allow(clippy::needless_return)]
+
+pub mod cartan;
+pub mod certificate;
+pub mod checkpoint;
+pub mod confluence;
+pub mod derivation;
+pub mod driver;
+pub mod error;
+pub mod integer;
+pub mod lyndon;
+pub mod parser;
+pub mod repl;
+pub mod ring;
+pub mod script;
+pub mod search;
+pub mod termination;
+pub mod trace;
+pub mod value;
+pub mod v2;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use integer::Integer;
+use lyndon::Lie;
+use parser::{Guard, Rule};
+use ring::{Coefficients, Integers, Rationals, Ring};
+use trace::{Action, Step, Trace};
+use value::*;
+
+pub use cartan::CartanMatrix;
+pub use error::ProofError;
+pub use parser::AST;
+pub use search::{Report, Search, Walk};
+pub use value::Value;
+
+#[derive(Clone)]
+pub struct Scope {
+    vars: HashMap<String, Value>,
+    cartan: Arc<CartanMatrix>,
+    coefficients: Coefficients,
+}
+
+impl Scope {
+    pub fn new(cartan: &Arc<CartanMatrix>) -> Scope {
+        return Scope { vars: HashMap::new(), cartan: cartan.clone(), coefficients: Coefficients::Integers };
+    }
+
+    /// Collects terms over another coefficient ring than the integers.
+    pub fn with_coefficients(mut self, coefficients: Coefficients) -> Scope {
+        self.coefficients = coefficients;
+        return self;
+    }
+
+    fn set(&mut self, name: &str, value: Value) {
+        self.vars.insert(name.to_string(), value);
+    }
+
+    fn get(&self, name: &str) -> Option<Value> {
+        return self.vars.get(name).cloned();
+    }
+
+    fn get_number(&self, name: &str) -> Result<i32, ProofError> {
+        let value = self.get(name).ok_or_else(|| ProofError::Unbound(name.to_string()))?;
+        let index = value
+            .into_number()
+            .and_then(|num| num.to_i32())
+            .ok_or_else(|| ProofError::Mismatch { what: name.to_string(), expected: "a generator index", found: value.to_string() })?;
+
+        return self.generator(index);
+    }
+
+    /// `index` if it labels a generator of the Cartan matrix.
+    fn generator(&self, index: i32) -> Result<i32, ProofError> {
+        if index < 1 || index as usize > self.cartan.rank() {
+            return Err(ProofError::OutOfRange { index, rank: self.cartan.rank() });
+        }
+
+        return Ok(index);
+    }
+}
+
+/// Matches `pattern` against `value`, calling `k` with the bindings of each
+/// way it matches until `k` accepts one. Bindings made here are undone
+/// before returning false, so a failed branch leaves `scope` as it was.
+///
+/// Sums match modulo associativity and commutativity, and a product with a
+/// number on either side matches either way round.
+fn ast_match_with(pattern: &AST, value: &Value, scope: &mut Scope, k: &mut dyn FnMut(&mut Scope) -> bool) -> bool {
+    match pattern {
+        AST::Named(name) => {
+            if let Some(current) = scope.vars.get(name) {
+                return value == current && k(scope);
+            }
+
+            scope.set(name, value.clone());
+
+            if k(scope) {
+                return true;
+            }
+
+            scope.vars.remove(name);
+            return false;
+        }
+        AST::Value(current) => return current == value && k(scope),
+        AST::Braket(a, b) => {
+            if let Value::Braket(va, vb) = value {
+                return ast_match_with(a, va, scope, &mut |scope| ast_match_with(b, vb, scope, k));
+            }
+
+            return false;
+        }
+        AST::Kind(name, avalue) => {
+            if let Value::Kind(vname, vvalue) = value {
+                if name == vname {
+                    return ast_match_with(&AST::Named(avalue.clone()), &Value::Number(Integer::from(*vvalue)), scope, k);
+                }
+            }
+
+            return false;
+        }
+        AST::Negative(pattern) => {
+            if let Value::Negative(value) = value {
+                return ast_match_with(pattern, value, scope, k);
+            }
+
+            return false;
+        }
+        AST::Add(_, _) => {
+            if let Value::Add(_, _) = value {
+                let patterns = pattern_terms(pattern);
+                let terms = sum_terms(value);
+
+                return match_terms(&patterns, &terms, &mut vec![false; terms.len()], false, scope, &mut |scope, _| k(scope));
+            }
+
+            return false;
+        }
+        AST::Mul(a, b) => {
+            if let Value::Mul(va, vb) = value {
+                if ast_match_with(a, va, scope, &mut |scope| ast_match_with(b, vb, scope, k)) {
+                    return true;
+                }
+
+                // Scalars commute, anything else keeps its order.
+                if va.into_number().is_some() || vb.into_number().is_some() {
+                    return ast_match_with(a, vb, scope, &mut |scope| ast_match_with(b, va, scope, k));
+                }
+            }
+
+            return false;
+        }
+        // `RuleSet` turns these away when it loads the rules.
+        AST::C(_, _) => return false,
+    }
+}
+
+/// The terms of a sum, however it is bracketed.
+fn sum_terms(value: &Value) -> Vec<Value> {
+    if let Value::Add(a, b) = value {
+        return [sum_terms(a), sum_terms(b)].concat();
+    }
+
+    return vec![value.clone()];
+}
+
+/// The terms of a sum pattern, with the free standing variables last so that
+/// every other term has bound what it can before they pick their terms.
+fn pattern_terms(pattern: &AST) -> Vec<&AST> {
+    fn flatten<'a>(pattern: &'a AST, out: &mut Vec<&'a AST>) {
+        if let AST::Add(a, b) = pattern {
+            flatten(a, out);
+            flatten(b, out);
+        } else {
+            out.push(pattern);
+        }
+    }
+
+    let mut out = vec![];
+    flatten(pattern, &mut out);
+    out.sort_by_key(|pattern| matches!(pattern, AST::Named(_)));
+
+    return out;
+}
+
+fn sum(terms: Vec<Value>) -> Value {
+    return terms
+        .into_iter()
+        .rev()
+        .reduce(|sum, term| Value::Add(Box::new(term), Box::new(sum)))
+        .unwrap_or(Value::zero());
+}
+
+/// Adds back the terms a sum pattern did not match, keeping the sum right
+/// nested.
+fn with_leftover(value: Value, leftover: Vec<Value>) -> Value {
+    if leftover.is_empty() {
+        return value;
+    }
+
+    return sum([sum_terms(&value), leftover].concat());
+}
+
+/// Matches each pattern term to a different unused term of a sum. The last
+/// pattern term, if it is a free variable, takes all the terms still unused,
+/// unless `extension` is set, in which case unused terms are allowed and
+/// passed on to `k`.
+fn match_terms(
+    patterns: &[&AST],
+    terms: &[Value],
+    used: &mut [bool],
+    extension: bool,
+    scope: &mut Scope,
+    k: &mut dyn FnMut(&mut Scope, Vec<Value>) -> bool,
+) -> bool {
+    let unused = |used: &[bool]| terms
+        .iter()
+        .zip(used)
+        .filter(|(_, used)| !**used)
+        .map(|(term, _)| term.clone())
+        .collect::<Vec<Value>>();
+
+    let Some((pattern, rest)) = patterns.split_first() else {
+        let leftover = unused(used);
+
+        if !leftover.is_empty() && !extension {
+            return false;
+        }
+
+        return k(scope, leftover);
+    };
+
+    if let AST::Named(name) = pattern {
+        if rest.is_empty() && !extension && !scope.vars.contains_key(name) {
+            let leftover = unused(used);
+
+            if leftover.is_empty() {
+                return false;
+            }
+
+            return ast_match_with(pattern, &sum(leftover), scope, &mut |scope| k(scope, vec![]));
+        }
+    }
+
+    for i in 0..terms.len() {
+        if used[i] {
+            continue;
+        }
+
+        used[i] = true;
+        let found = ast_match_with(pattern, &terms[i], scope, &mut |scope| match_terms(rest, terms, used, extension, scope, k));
+        used[i] = false;
+
+        if found {
+            return true;
+        }
+    }
+
+    return false;
+}
+
+/// Matches a rule at the top of `value`, guards included. A sum pattern may
+/// match only some of the terms of a sum, and the terms it leaves over are
+/// returned to be added back to the rewritten result.
+fn ast_match_rule(rule: &Rule, pattern: &AST, value: &Value, scope: &mut Scope) -> Result<Option<Vec<Value>>, ProofError> {
+    let mut leftover = None;
+    let mut failed = None;
+
+    let mut accept = |scope: &mut Scope, rest: Vec<Value>| {
+        match guards_hold(rule, scope) {
+            Ok(true) => leftover = Some(rest),
+            Ok(false) => return false,
+            // Stops matching, the same as a match would.
+            Err(err) => failed = Some(err),
+        }
+
+        return true;
+    };
+
+    if let (AST::Add(_, _), Value::Add(_, _)) = (pattern, value) {
+        let patterns = pattern_terms(pattern);
+        let terms = sum_terms(value);
+
+        match_terms(&patterns, &terms, &mut vec![false; terms.len()], true, scope, &mut accept);
+    } else {
+        ast_match_with(pattern, value, scope, &mut |scope| accept(scope, vec![]));
+    }
+
+    if let Some(err) = failed {
+        return Err(err);
+    }
+
+    return Ok(leftover);
+}
+
+fn ast_number(expresion: &AST, scope: &Scope) -> Result<i32, ProofError> {
+    let value = ast_build(expresion, scope)?;
+    let index = value.into_number().and_then(|num| num.to_i32());
+
+    return index.ok_or_else(|| ProofError::Mismatch { what: expresion.to_string(), expected: "a generator index", found: value.to_string() });
+}
+
+fn ast_build(expresion: &AST, scope: &Scope) -> Result<Value, ProofError> {
+    let value = match expresion {
+        AST::Named(name) => scope.get(name).ok_or_else(|| ProofError::Unbound(name.clone()))?,
+        AST::Value(Value::Kind(name, index)) => Value::Kind(name.clone(), scope.generator(*index)?),
+        AST::Value(value) => value.clone(),
+        AST::Kind(name, value) => Value::Kind(name.clone(), scope.get_number(value)?),
+        AST::Negative(expresion) => Value::Negative(Box::new(ast_build(expresion, scope)?)),
+        AST::Braket(a, b) => Value::Braket(
+            Box::new(ast_build(a, scope)?),
+            Box::new(ast_build(b, scope)?)
+        ),
+        AST::Add(a, b) => Value::Add(
+            Box::new(ast_build(a, scope)?),
+            Box::new(ast_build(b, scope)?)
+        ),
+        AST::Mul(a, b) => Value::Mul(
+            Box::new(ast_build(a, scope)?),
+            Box::new(ast_build(b, scope)?)
+        ),
+        AST::C(a, b) => {
+            number(Integer::from(scope.cartan.entry(ast_number(a, scope)?, ast_number(b, scope)?)?))
+        }
+    };
+
+    return Ok(value);
+}
+
+fn guards_hold(rule: &Rule, scope: &Scope) -> Result<bool, ProofError> {
+    for guard in &rule.guards {
+        let holds = match guard {
+            Guard::Equal(a, b) => ast_build(a, scope)? == ast_build(b, scope)?,
+            Guard::NotEqual(a, b) => ast_build(a, scope)? != ast_build(b, scope)?,
+        };
+
+        if !holds {
+            return Ok(false);
+        }
+    }
+
+    return Ok(true);
+}
+
+/// Nested rewrites `rewrite` does before deciding the rules loop, well short
+/// of overflowing the stack.
+const MAX_DEPTH: usize = 2000;
+
+/// Rewrites `simplify` does in all before giving up.
+const MAX_STEPS: usize = 10_000_000;
+
+/// Rounds of rewriting and collecting terms `reduce` does before deciding
+/// they undo each other.
+const MAX_ROUNDS: usize = 16;
+
+/// Why `rewrite` gave up.
+#[derive(Debug)]
+pub enum Stop {
+    Steps,
+    Depth,
+    /// Rewriting a term came back round to the same term, through these
+    /// rules, by index.
+    Loop(Vec<usize>),
+    /// A rule's guard or right-hand side could not be built.
+    Invalid(ProofError),
+    /// Rewriting and collecting terms kept undoing each other.
+    Unsettled,
+}
+
+impl std::fmt::Display for Stop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stop::Steps => write!(f, "ran out of rewrites"),
+            Stop::Depth => write!(f, "went more than {MAX_DEPTH} rewrites deep"),
+            Stop::Loop(rules) => {
+                let rules = rules.iter().map(|i| i.to_string()).collect::<Vec<String>>();
+                write!(f, "loops through rules {}", rules.join(", "))
+            }
+            Stop::Invalid(err) => write!(f, "{err}"),
+            Stop::Unsettled => write!(f, "and collecting terms kept undoing each other"),
+        }
+    }
+}
+
+impl Stop {
+    /// What went wrong, with the rules of a loop written out.
+    pub fn explain(&self, patterns: &[Rule]) -> String {
+        let Stop::Loop(rules) = self else {
+            return format!("Rewriting {self}!");
+        };
+
+        let mut out = "Rewriting loops through the rules".to_string();
+        let mut seen = vec![];
+
+        for i in rules {
+            if !seen.contains(i) {
+                out += &format!("\n    {i}: {}", patterns[*i]);
+                seen.push(*i);
+            }
+        }
+
+        return out;
+    }
+
+    /// The error to report: what went wrong building a term as it is, or
+    /// `explain` for rewriting that does not finish.
+    pub fn error(self, patterns: &[Rule]) -> ProofError {
+        if let Stop::Invalid(err) = self {
+            return err;
+        }
+
+        return ProofError::Rewrite(self.explain(patterns));
+    }
+}
+
+/// The rewrites `rewrite` has left, and the redexes it is in the middle of
+/// rewriting with the rule applied to each. Rewriting is deterministic, so
+/// meeting one of those redexes again means it never finishes.
+///
+/// Also where in the whole term rewriting has got to, and the steps taken
+/// if asked to keep them.
+pub struct Budget {
+    steps: usize,
+    chain: Vec<(Value, usize)>,
+    position: Vec<usize>,
+    trace: Option<Vec<Step>>,
+}
+
+impl Budget {
+    pub fn new(steps: usize) -> Budget {
+        return Budget { steps, chain: vec![], position: vec![], trace: None };
+    }
+
+    /// Keeps every step, for `Trace`.
+    pub fn traced(mut self) -> Budget {
+        self.trace = Some(vec![]);
+        return self;
+    }
+
+    fn enter(&mut self, redex: Value, rule: usize) -> Result<(), Stop> {
+        if let Some(at) = self.chain.iter().position(|(value, _)| *value == redex) {
+            return Err(Stop::Loop(self.chain[at..].iter().map(|(_, rule)| *rule).collect()));
+        }
+
+        if self.steps == 0 {
+            return Err(Stop::Steps);
+        }
+
+        if self.chain.len() >= MAX_DEPTH {
+            return Err(Stop::Depth);
+        }
+
+        self.steps -= 1;
+        self.chain.push((redex, rule));
+
+        return Ok(());
+    }
+
+    /// Records a step at the current position, building its terms only when
+    /// they are kept.
+    fn record(&mut self, action: Action, scope: Option<&Scope>, terms: impl FnOnce() -> (Value, Value)) {
+        let Some(trace) = &mut self.trace else {
+            return;
+        };
+
+        let mut substitution = scope
+            .map(|scope| scope.vars.iter().map(|(name, value)| (name.clone(), value.clone())).collect::<Vec<(String, Value)>>())
+            .unwrap_or_default();
+        substitution.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let (before, after) = terms();
+        trace.push(Step { action, position: self.position.clone(), substitution, before, after });
+    }
+}
+
+/// `ast_match_rule` with the bracket in the rule's pattern flipped, if it
+/// has one.
+fn flipped_match(rule: &Rule, value: &Value, scope: &mut Scope) -> Result<Option<Vec<Value>>, ProofError> {
+    match rule.pattern.flip() {
+        Some(pattern) => return ast_match_rule(rule, &pattern, value, scope),
+        None => return Ok(None),
+    }
+}
+
+fn ast_match_and_build(value: Value, patterns: &Vec<Rule>, cartan: &Arc<CartanMatrix>, budget: &mut Budget) -> Result<Value, Stop> {
+    for (index, rule) in patterns.iter().enumerate() {
+        let scope = &mut Scope::new(cartan);
+
+        let built = if let Some(leftover) = ast_match_rule(rule, &rule.pattern, &value, scope).map_err(Stop::Invalid)? {
+            with_leftover(ast_build(&rule.expr, scope).map_err(Stop::Invalid)?, leftover)
+        } else if let Some(leftover) = flipped_match(rule, &value, scope).map_err(Stop::Invalid)? {
+            with_leftover(ast_build(&rule.expr.negate(), scope).map_err(Stop::Invalid)?, leftover)
+        } else {
+            continue;
+        };
+
+        budget.record(Action::Rule(index), Some(scope), || (value.clone(), built.clone()));
+        budget.enter(value, index)?;
+        let result = rewrite(built, patterns, cartan, budget);
+        budget.chain.pop();
+
+        return result;
+    }
+
+    return Ok(value);
+}
+
+/// Rewrites the `i`th child of the term at the current position.
+fn rewrite_child(value: Value, i: usize, patterns: &Vec<Rule>, cartan: &Arc<CartanMatrix>, budget: &mut Budget) -> Result<Value, Stop> {
+    budget.position.push(i);
+    let result = rewrite(value, patterns, cartan, budget);
+    budget.position.pop();
+
+    return result;
+}
+
+/// Rewrites `value` until no rule applies, or says why it could not.
+fn rewrite(value: Value, patterns: &Vec<Rule>, cartan: &Arc<CartanMatrix>, budget: &mut Budget) -> Result<Value, Stop> {
+    match value {
+        Value::Add(a, b) => {
+            let (a, b) = (rewrite_child(*a, 0, patterns, cartan, budget)?, rewrite_child(*b, 1, patterns, cartan, budget)?);
+
+            if let (Some(n), Some(m)) = (a.into_number(), b.into_number()) {
+                budget.record(Action::Arithmetic, None, || (Value::Add(Box::new(a), Box::new(b)), number(n.clone() + m.clone())));
+                return Ok(number(n + m));
+            }
+
+            return ast_match_and_build(Value::Add(Box::new(a), Box::new(b)), patterns, cartan, budget)
+        }
+        Value::Negative(value) => {
+            return ast_match_and_build(Value::Negative(Box::new(rewrite_child(*value, 0, patterns, cartan, budget)?)), patterns, cartan, budget)
+        }
+        Value::Mul(a, b) => {    
+            let (a, b) = (rewrite_child(*a, 0, patterns, cartan, budget)?, rewrite_child(*b, 1, patterns, cartan, budget)?);
+
+            if let (Some(n), Some(m)) = (a.into_number(), b.into_number()) {
+                budget.record(Action::Arithmetic, None, || (Value::Mul(Box::new(a), Box::new(b)), number(n.clone() * m.clone())));
+                return Ok(number(n * m));
+            }
+
+            // Scalars go on the left, where rules like `[n * a, b]` expect
+            // them, even after matching a product the other way round.
+            if b.into_number().is_some() {
+                budget.record(Action::Arithmetic, None, || (Value::Mul(Box::new(a.clone()), Box::new(b.clone())), Value::Mul(Box::new(b.clone()), Box::new(a.clone()))));
+                return ast_match_and_build(Value::Mul(Box::new(b), Box::new(a)), patterns, cartan, budget)
+            }
+
+            return ast_match_and_build(Value::Mul(Box::new(a), Box::new(b)), patterns, cartan, budget)
+        }
+        Value::Braket(a, b) => {
+            let (a, b) = (rewrite_child(*a, 0, patterns, cartan, budget)?, rewrite_child(*b, 1, patterns, cartan, budget)?);
+            return ast_match_and_build(Value::Braket(Box::new(a), Box::new(b)), patterns, cartan, budget)
+        }
+        Value::Number(_) => return Ok(value),
+        Value::Kind(_, _) => return Ok(value),
+    }
+}
+
+/// Rewrites and collects terms until neither changes anything. Collecting
+/// rebrackets terms in the Lyndon basis, which can make new redexes, so
+/// each collection that changes the term is a step of its own.
+fn reduce(mut value: Value, rules: &Vec<Rule>, cartan: &Arc<CartanMatrix>, coefficients: Coefficients, budget: &mut Budget) -> Result<Value, Stop> {
+    for _ in 0..MAX_ROUNDS {
+        let rewritten = rewrite(value.clone(), rules, cartan, budget)?;
+        let collected = is_lots_of_es_zero(rewritten.clone(), coefficients).map_err(Stop::Invalid)?;
+
+        if collected != rewritten {
+            budget.record(Action::Collect, None, || (rewritten, collected.clone()));
+        }
+
+        if collected == value {
+            return Ok(value);
+        }
+
+        value = collected;
+    }
+
+    return Err(Stop::Unsettled);
+}
+
+fn standerdize(value: Value) -> Value {
+    match value {
+        Value::Braket(a, b) => {
+            match (a.as_ref(), b.as_ref()) {
+                (Value::Kind(_, na), Value::Kind(_, nb)) => {
+                    if na > nb {
+                        Value::Braket(a, b)
+                    } else {
+                        Value::Negative(Box::new(Value::Braket(b, a)))
+                    }
+                }
+                (k @ Value::Kind(_, _),  b @ Value::Braket(_, _)) => {
+                    match standerdize(b.clone()) {
+                        Value::Negative(b) => Value::Braket(Box::new(standerdize(*b)), Box::new(k.clone())),
+                        b => Value::Negative(Box::new(Value::Braket(Box::new(standerdize(b.clone())), Box::new(k.clone())))),
+                    }
+                }
+                (b @ Value::Braket(_, _), k @ Value::Kind(_, _)) => {
+                    match standerdize(b.clone()) {
+                        Value::Negative(b) => Value::Negative(Box::new(Value::Braket(Box::new(standerdize(*b)), Box::new(k.clone())))),
+                        b => Value::Braket(Box::new(standerdize(b.clone())), Box::new(k.clone())),
+                    }
+                }
+                _ => Value::Braket(a, b)
+            }
+        }
+        _ => value
+    }
+}
+
+fn value_to_lie(value: &Value) -> Option<Lie<(String, i32)>> {
+    match value {
+        Value::Kind(name, n) => Some(Lie::Letter((name.clone(), *n))),
+        Value::Braket(a, b) => Some(Lie::Braket(Box::new(value_to_lie(a)?), Box::new(value_to_lie(b)?))),
+        _ => None,
+    }
+}
+
+fn lie_to_value(lie: &Lie<(String, i32)>) -> Value {
+    match lie {
+        Lie::Letter((name, n)) => Value::Kind(name.clone(), *n),
+        Lie::Braket(a, b) => brak(lie_to_value(a), lie_to_value(b)),
+    }
+}
+
+/// A sum as numbers times values, with scalars multiplied out over sums.
+fn scaled_terms(value: Value) -> Result<Vec<(Integer, Value)>, ProofError> {
+    match value {
+        Value::Add(a, b) => return Ok([scaled_terms(*a)?, scaled_terms(*b)?].concat()),
+        Value::Negative(value) => return Ok(scaled_terms(*value)?.into_iter().map(|(n, value)| (-n, value)).collect()),
+        // Scalar products commute, so the number can be on either side.
+        Value::Mul(a, b) => {
+            let (n, value) = match (a.into_number(), b.into_number()) {
+                (Some(n), _) => (n, *b),
+                (None, Some(n)) => (n, *a),
+                (None, None) => return Err(ProofError::NonNumeric(AST::from_value(&Value::Mul(a, b)).to_string())),
+            };
+
+            return Ok(scaled_terms(value)?.into_iter().map(|(m, value)| (n.clone() * m, value)).collect());
+        }
+        value => return Ok(vec![(Integer::one(), value)]),
+    }
+}
+
+fn is_lots_of_es_zero(value: Value, coefficients: Coefficients) -> Result<Value, ProofError> {
+    let es = scaled_terms(value)?;
+
+    match coefficients {
+        Coefficients::Integers => return collect_terms(es, &Integers),
+        Coefficients::Rationals => return collect_terms(es, &Rationals),
+        Coefficients::Modular(ring) => return collect_terms(es, &ring),
+    }
+}
+
+fn collect_terms<R: Ring>(es: Vec<(Integer, Value)>, ring: &R) -> Result<Value, ProofError> {
+    // Brackets of generators are rewritten in the Lyndon basis, where equal
+    // elements have equal coordinates, anything else is merged as it stands.
+    let mut lie = vec![];
+    let mut rest = vec![];
+
+    for (n, e) in es {
+        match value_to_lie(&e) {
+            Some(l) => lie.push((ring.lift(&n), l)),
+            None => rest.push((n, e)),
+        }
+    }
+
+    let mut es = lyndon::normalize(&lie, ring)?.into_iter()
+        .map(|(n, word)| (n, lie_to_value(&lyndon::standard_bracketing(&word))))
+        .collect::<Vec<(R::Elem, Value)>>();
+
+    es.extend(rest.into_iter().map(|(n, e)| match standerdize(e) {
+        Value::Negative(e) => (ring.lift(&-n), *e),
+        e => (ring.lift(&n), e),
+    }));
+
+    for i in (1..es.len()).rev() {
+        for j in 0..i {
+            if es[i].1 == es[j].1 {
+                es[j].0 = ring.add(&es[j].0, &es[i].0);
+                es.remove(i);
+                break;
+            }
+        }
+    }
+
+    let es = es.into_iter()
+        .filter(|(n, _)| !ring.is_zero(n))
+        .map(|(n, e)| Ok((ring.to_integer(&n).ok_or_else(|| ProofError::NotInteger(n.to_string()))?, e)))
+        .collect::<Result<Vec<(Integer, Value)>, ProofError>>()?;
+
+    return Ok(es.into_iter()
+        .map(|(n, e)| if n.is_one() {
+            e
+        } else if (-n.clone()).is_one() {
+            Value::Negative(Box::new(e))
+        } else if !n.is_negative() {
+            Value::Mul(Box::new(Value::Number(n)), Box::new(e))
+        } else {
+            Value::Negative(Box::new(Value::Mul(Box::new(Value::Number(-n)), Box::new(e))))
+        })
+        .reduce(|p, v| Value::Add(Box::new(p), Box::new(v)))
+        .unwrap_or(Value::zero()));
+}
+
+/// What a check of a file found, to be printed, and whether it holds.
+#[derive(Clone, Debug)]
+pub struct Verdict {
+    pub holds: bool,
+    pub text: String,
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{}", self.text);
+    }
+}
+
+/// The rules `simplify` rewrites with: the Serre relations of a Cartan
+/// matrix, then the rules of a rule file. Rule indices, in traces and loop
+/// reports, count from the first Serre relation.
+#[derive(Clone, Debug)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    /// Loads a rule file and puts the Serre relations of the Cartan matrix
+    /// in front of it, so that they fire before any expansion rule.
+    pub fn load(path: &str, cartan: &CartanMatrix) -> Result<RuleSet, ProofError> {
+        return RuleSet::after_serre(parser::load(path)?, cartan);
+    }
+
+    /// `load`, keeping only the rules of the given sections of the file.
+    /// The Serre relations are always kept, and a section with no rules is
+    /// taken for a typo.
+    pub fn load_sections(path: &str, sections: &[&str], cartan: &CartanMatrix) -> Result<RuleSet, ProofError> {
+        let rules = parser::load_sections(path, sections)?;
+
+        if let Some(section) = sections.iter().find(|section| !rules.iter().any(|rule| rule.section == **section)) {
+            return Err(ProofError::NoSection { path: path.to_string(), section: section.to_string() });
+        }
+
+        return RuleSet::after_serre(rules, cartan);
+    }
+
+    fn after_serre(rules: Vec<Rule>, cartan: &CartanMatrix) -> Result<RuleSet, ProofError> {
+        if let Some(rule) = rules.iter().find(|rule| rule.pattern.has_c()) {
+            return Err(ProofError::CInPattern(rule.to_string()));
+        }
+
+        let mut all = RuleSet::serre(cartan).rules;
+        all.extend(rules);
+
+        return Ok(RuleSet { rules: all });
+    }
+
+    /// The Serre relations of the Cartan matrix alone.
+    pub fn serre(cartan: &CartanMatrix) -> RuleSet {
+        let rules = ["E", "F"]
+            .iter()
+            .flat_map(|kind| cartan.serre_relations(kind))
+            .map(|relation| Rule::new(AST::Value(relation), AST::Value(Value::zero())))
+            .collect::<Vec<Rule>>();
+
+        return RuleSet { rules };
+    }
+}
+
+impl std::ops::Deref for RuleSet {
+    type Target = Vec<Rule>;
+
+    fn deref(&self) -> &Vec<Rule> {
+        return &self.rules;
+    }
+}
+
+/// Rewrites `value` with `rules` until no rule applies.
+pub fn simplify(value: Value, rules: &RuleSet, cartan: &Arc<CartanMatrix>) -> Result<Value, ProofError> {
+    return rewrite(value, rules, cartan, &mut Budget::new(MAX_STEPS)).map_err(|stop| stop.error(rules));
+}
+
+/// Rewrites `value` with `rules` and collects its terms, until neither
+/// changes anything.
+pub fn normal_form(value: Value, rules: &RuleSet, scope: &Scope) -> Result<Value, ProofError> {
+    return reduce(value, rules, &scope.cartan, scope.coefficients, &mut Budget::new(MAX_STEPS)).map_err(|stop| stop.error(rules));
+}
+
+pub fn str_build(str: &str, cartan: &Arc<CartanMatrix>) -> Result<Value, ProofError> {
+    return ast_build(&parser::parse(str)?, &Scope::new(cartan));
+}
+
+pub fn check_conter_example_from_string(str: &str, rules: &RuleSet, cartan: &Arc<CartanMatrix>) -> Result<Value, ProofError> {
+    let ast = str_build(str, cartan)?;
+    return normal_form(ast, rules, &Scope::new(cartan));
+}
+
+pub fn make(src: &str, rules: &RuleSet, scope: &Scope) -> Result<Value, ProofError> {
+    return normal_form(ast_build(&parser::parse(src)?, scope)?, rules, scope);
+}
+
+/// `make`, keeping every step on the way.
+pub fn make_traced(src: &str, rules: &RuleSet, scope: &Scope) -> Result<Trace, ProofError> {
+    return simplify_traced(ast_build(&parser::parse(src)?, scope)?, rules, scope);
+}
+
+/// `normal_form`, keeping every step on the way.
+pub fn simplify_traced(start: Value, rules: &RuleSet, scope: &Scope) -> Result<Trace, ProofError> {
+    let mut budget = Budget::new(MAX_STEPS).traced();
+    let result = reduce(start.clone(), rules, &scope.cartan, scope.coefficients, &mut budget).map_err(|stop| stop.error(rules))?;
+
+    return Ok(Trace { start, steps: budget.trace.unwrap_or_default(), result });
+}
+
+/// `[N, F(b)]` for `N = [...[[E(w1), E(w2)], E(w3)], ...]`, through the
+/// rewrite rules. Much slower than `search::Walk`, but a check on it.
+pub fn n_f(word: &[i32], b: i32, rules: &RuleSet, cartan: &Arc<CartanMatrix>) -> Result<Value, ProofError> {
+    let (first, rest) = word.split_first().ok_or(ProofError::EmptyWord)?;
+
+    let mut scope = Scope::new(cartan);
+    scope.set("b", Value::Number(Integer::from(b)));
+    scope.set("n", Value::Number(Integer::from(*first)));
+
+    let mut nx   = make("E(n)", rules, &scope)?;
+    let mut nx_f = make("[E(n), F(b)]", rules, &scope)?;
+    let mut nx_h = make("[E(n), H(b)]", rules, &scope)?;
+
+    for n in rest {
+        scope.set("n", Value::Number(Integer::from(*n)));
+        scope.set("nx", nx.clone());
+        scope.set("nx_f", nx_f);
+        scope.set("nx_h", nx_h);
+
+        nx = brak(nx, e(*n));
+
+        nx_f = if *n == b {
+            make("[nx_f, E(n)] + nx_h", rules, &scope)?
+        } else {
+            make("[nx_f, E(n)]", rules, &scope)?
+        };
+
+        nx_h = make(
+            "[nx_h, E(n)] - C(b, n) * [nx, E(n)]",
+            rules, &scope
+        )?;
+    }
+
+    return Ok(nx_f);
+}
+
+/// Whether `[N, F(b)]` vanishes for every `b`, through the rewrite rules.
+pub fn find_conter_exmaple(word: &[i32], rules: &RuleSet, cartan: &Arc<CartanMatrix>) -> Result<bool, ProofError> {
+    for b in 1..=cartan.rank() as i32 {
+        if n_f(word, b, rules, cartan)? != Value::zero() {
+            return Ok(false);
+        }
+    }
+
+    return Ok(true);
+}
+
+///////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn rules(src: &[(&str, &str)]) -> Vec<Rule> {
+        return src.iter().map(|(pattern, expr)| Rule::new(parse(pattern).unwrap(), parse(expr).unwrap())).collect();
+    }
+
+    fn a2() -> Arc<CartanMatrix> {
+        return Arc::new(CartanMatrix::dynkin('A', 2).unwrap());
+    }
+
+    #[test]
+    fn swapping_rules_are_caught_looping() {
+        let rules = rules(&[("[a, b]", "[b, a]")]);
+        let result = rewrite(brak(e(1), e(2)), &rules, &a2(), &mut Budget::new(MAX_STEPS));
+
+        assert!(matches!(result, Err(Stop::Loop(ref through)) if through == &[0, 0]), "{result:?}");
+    }
+
+    #[test]
+    fn growing_rules_run_out_of_steps() {
+        // Every redex is new, so this never loops back to a term it has seen.
+        let rules = rules(&[("-a", "-(a + 0)")]);
+        let start = Value::Negative(Box::new(e(1)));
+
+        let result = rewrite(start, &rules, &a2(), &mut Budget::new(100));
+        assert!(matches!(result, Err(Stop::Steps)), "{result:?}");
+    }
+
+    #[test]
+    fn steps_within_the_budget_finish() {
+        let rules = rules(&[("--a", "a")]);
+        let start = (0..20).fold(e(1), |value, _| Value::Negative(Box::new(value)));
+
+        assert!(matches!(rewrite(start.clone(), &rules, &a2(), &mut Budget::new(5)), Err(Stop::Steps)));
+        assert_eq!(rewrite(start, &rules, &a2(), &mut Budget::new(10)).unwrap(), e(1));
+    }
+
+    fn guarded(pattern: &str, expr: &str, guard: Guard) -> Vec<Rule> {
+        return vec![Rule { guards: vec![guard], ..rules(&[(pattern, expr)]).remove(0) }];
+    }
+
+    #[test]
+    fn guards_decide_whether_a_rule_fires() {
+        let ast = |src| parse(src).unwrap();
+        let rules = guarded("[E(a), F(b)]", "0", Guard::NotEqual(ast("a"), ast("b")));
+        let rewritten = |value| rewrite(value, &rules, &a2(), &mut Budget::new(MAX_STEPS)).unwrap();
+
+        assert_eq!(rewritten(brak(e(1), f(2))), Value::zero());
+        assert_eq!(rewritten(brak(e(2), f(1))), Value::zero());
+        assert_eq!(rewritten(brak(e(1), f(1))), brak(e(1), f(1)));
+
+        // Guards can read the Cartan matrix: E(1) and E(3) commute in A3.
+        let a3 = Arc::new(CartanMatrix::dynkin('A', 3).unwrap());
+        let rules = guarded("[E(a), E(b)]", "0", Guard::Equal(ast("C(a, b)"), ast("0")));
+        let rewritten = |value| rewrite(value, &rules, &a3, &mut Budget::new(MAX_STEPS)).unwrap();
+
+        assert_eq!(rewritten(brak(e(1), e(3))), Value::zero());
+        assert_eq!(rewritten(brak(e(1), e(2))), brak(e(1), e(2)));
+    }
+
+    #[test]
+    fn sums_match_in_any_order_and_grouping() {
+        let rules = rules(&[("E(a) + F(a)", "H(a)")]);
+        let rewritten = |value| AST::from_value(&rewrite(value, &rules, &a2(), &mut Budget::new(MAX_STEPS)).unwrap()).to_string();
+        let add = |a, b| Value::Add(Box::new(a), Box::new(b));
+
+        assert_eq!(rewritten(add(e(1), f(1))), "H(1)");
+        assert_eq!(rewritten(add(f(1), e(1))), "H(1)");
+        assert_eq!(rewritten(add(add(e(2), f(1)), e(1))), "H(1) + E(2)");
+        assert_eq!(rewritten(add(f(1), add(e(2), e(1)))), "H(1) + E(2)");
+        assert_eq!(rewritten(add(f(1), e(2))), "F(1) + E(2)");
+    }
+
+    #[test]
+    fn scalar_products_match_either_way_round() {
+        let times = |a, b| Value::Mul(Box::new(a), Box::new(b));
+
+        for (pattern, value) in [("x * 2", times(number(Integer::from(2)), e(1))), ("2 * x", times(e(1), number(Integer::from(2))))] {
+            let rules = rules(&[(pattern, "x + x")]);
+            let rewritten = rewrite(value, &rules, &a2(), &mut Budget::new(MAX_STEPS)).unwrap();
+
+            assert_eq!(AST::from_value(&rewritten).to_string(), "E(1) + E(1)", "{pattern}");
+        }
+
+        // The matcher itself, before rewriting moves the number to the left.
+        let rule = rules(&[("2 * x", "x + x")]).remove(0);
+        let matches = |value: &Value| ast_match_rule(&rule, &rule.pattern, value, &mut Scope::new(&a2())).unwrap().is_some();
+
+        assert!(matches(&times(e(1), number(Integer::from(2)))));
+        assert!(matches(&times(number(Integer::from(2)), e(1))));
+        assert!(!matches(&times(e(1), number(Integer::from(3)))));
+    }
+}
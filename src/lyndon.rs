@@ -0,0 +1,159 @@
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+
+use crate::error::ProofError;
+use crate::ring::Ring;
+
+/// A bracket polynomial term in the free Lie algebra over the letters `L`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Lie<L> {
+    Letter(L),
+    Braket(Box<Lie<L>>, Box<Lie<L>>),
+}
+
+/// A polynomial in the free associative algebra, keyed by word.
+pub type Poly<L, R> = BTreeMap<Vec<L>, <R as Ring>::Elem>;
+
+/// A combination of Lyndon basis elements, each given by its Lyndon word.
+pub type Basis<L, R> = Vec<(<R as Ring>::Elem, Vec<L>)>;
+
+/// Expands a bracket into the free associative algebra, `[a, b] = ab - ba`.
+///
+/// A bracket of depth `d` expands to up to `2^d` words, so this, and
+/// `normalize` with it, is only for brackets a few levels deep.
+pub fn expand<L: Ord + Clone, R: Ring>(lie: &Lie<L>, ring: &R) -> Poly<L, R> {
+    match lie {
+        Lie::Letter(letter) => {
+            return BTreeMap::from([(vec![letter.clone()], ring.lift_i32(1))]);
+        }
+        Lie::Braket(a, b) => {
+            let a = expand(a, ring);
+            let b = expand(b, ring);
+
+            let mut poly = BTreeMap::new();
+
+            for (wa, na) in &a {
+                for (wb, nb) in &b {
+                    let n = ring.mul(na, nb);
+                    add_term(&mut poly, [wa.clone(), wb.clone()].concat(), n.clone(), ring);
+                    add_term(&mut poly, [wb.clone(), wa.clone()].concat(), ring.neg(&n), ring);
+                }
+            }
+
+            return poly;
+        }
+    }
+}
+
+pub(crate) fn add_term<L: Ord, R: Ring>(poly: &mut Poly<L, R>, word: Vec<L>, n: R::Elem, ring: &R) {
+    match poly.entry(word) {
+        Entry::Occupied(mut entry) => {
+            let sum = ring.add(entry.get(), &n);
+
+            if ring.is_zero(&sum) {
+                entry.remove();
+            } else {
+                entry.insert(sum);
+            }
+        }
+        Entry::Vacant(entry) => if !ring.is_zero(&n) {
+            entry.insert(n);
+        }
+    }
+}
+
+/// A word is Lyndon when it is strictly smaller than all of its proper suffixes.
+pub fn is_lyndon<L: Ord>(word: &[L]) -> bool {
+    return !word.is_empty() && (1..word.len()).all(|i| word < &word[i..]);
+}
+
+/// The standard bracketing of a Lyndon word: `w = uv` with `v` the longest
+/// proper Lyndon suffix, bracketed as `[P(u), P(v)]`.
+pub fn standard_bracketing<L: Ord + Clone>(word: &[L]) -> Lie<L> {
+    if word.len() == 1 {
+        return Lie::Letter(word[0].clone());
+    }
+
+    let split = (1..word.len())
+        .find(|i| is_lyndon(&word[*i..]))
+        .expect("Every word of length two or more has a proper Lyndon suffix!");
+
+    return Lie::Braket(
+        Box::new(standard_bracketing(&word[..split])),
+        Box::new(standard_bracketing(&word[split..])),
+    );
+}
+
+/// Rewrites a sum of brackets as a combination of Lyndon basis elements.
+///
+/// The expansion of the standard bracketing of a Lyndon word `w` is `w` plus
+/// lexicographically larger words, so repeatedly cancelling the smallest
+/// remaining word of the expanded sum reads off the coordinates in the basis.
+/// Two sums are therefore equal exactly when they give the same result.
+pub fn normalize<L: Ord + Clone, R: Ring>(terms: &[(R::Elem, Lie<L>)], ring: &R) -> Result<Basis<L, R>, ProofError> {
+    let mut poly = BTreeMap::new();
+
+    for (n, lie) in terms {
+        for (word, m) in expand(lie, ring) {
+            add_term(&mut poly, word, ring.mul(n, &m), ring);
+        }
+    }
+
+    let mut basis = vec![];
+
+    while let Some((word, n)) = poly.iter().next().map(|(word, n)| (word.clone(), n.clone())) {
+        if !is_lyndon(&word) {
+            return Err(ProofError::NotLie);
+        }
+
+        for (w, m) in expand(&standard_bracketing(&word), ring) {
+            add_term(&mut poly, w, ring.neg(&ring.mul(&n, &m)), ring);
+        }
+
+        basis.push((n, word));
+    }
+
+    return Ok(basis);
+}
+
+///////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integer::Integer;
+    use crate::ring::{Integers, Modular};
+
+    fn letter(c: char) -> Lie<char> {
+        return Lie::Letter(c);
+    }
+
+    fn brak(a: Lie<char>, b: Lie<char>) -> Lie<char> {
+        return Lie::Braket(Box::new(a), Box::new(b));
+    }
+
+    #[test]
+    fn jacobi_identity_normalizes_to_zero() {
+        let (x, y, z) = (letter('x'), letter('y'), letter('z'));
+        let jacobi = [
+            (Integer::one(), brak(x.clone(), brak(y.clone(), z.clone()))),
+            (Integer::one(), brak(y.clone(), brak(z.clone(), x.clone()))),
+            (Integer::one(), brak(z.clone(), brak(x.clone(), y.clone()))),
+        ];
+
+        assert_eq!(normalize(&jacobi, &Integers).unwrap(), []);
+
+        let ring = Modular::new(7).unwrap();
+        let jacobi = jacobi.map(|(_, lie)| (1, lie));
+        assert_eq!(normalize(&jacobi, &ring).unwrap(), []);
+    }
+
+    #[test]
+    fn antisymmetry_lands_on_the_lyndon_word() {
+        let terms = [(Integer::from(2), brak(letter('b'), letter('a')))];
+
+        assert_eq!(normalize(&terms, &Integers).unwrap(), [(Integer::from(-2), vec!['a', 'b'])]);
+        assert_eq!(standard_bracketing(&['a', 'a', 'b']), brak(letter('a'), brak(letter('a'), letter('b'))));
+        assert!(is_lyndon(&['a', 'a', 'b']) && !is_lyndon(&['a', 'b', 'a']));
+    }
+}
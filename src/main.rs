@@ -1,388 +1,420 @@
-pub mod parser;
-pub mod value;
-pub mod v2;
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+
+use proof::driver::{self, SearchOptions};
+use proof::ring::Coefficients;
+use proof::{certificate, confluence, derivation, repl, script, search, termination, trace};
+use proof::{CartanMatrix, ProofError, RuleSet, Scope, Search, Verdict, AST};
+
+/// Exit codes, the same for every command.
+mod exit {
+    /// Everything checked out, or a search found nothing.
+    pub const OK: i32 = 0;
+    /// A check failed: rules that are not confluent or do not terminate, a
+    /// step that does not follow, a script that went wrong.
+    pub const FAILED: i32 = 1;
+    /// The command line made no sense.
+    pub const USAGE: i32 = 2;
+    /// A file or matrix could not be read.
+    pub const INPUT: i32 = 3;
+    /// A search found a candidate counter example.
+    pub const FOUND: i32 = 4;
+}
+
+const USAGE: &str = "\
+Usage: proof <command> [options]
+
+Commands:
+  simplify <expr>            simplify an expression with the rules
+      --trace                  print every rewrite
+      --derivation <file>      write the rewrites as a derivation
+      --certificate <file>     write the rewrites as a certificate
+  search                     look for words N with [N, F(i)] = 0 for all i
+      --word <words>           1,2,1,3 for one word, or word:1,2, cycle:1,2,3,
+                               all:8 or bfs:8; every generator in turn by default
+      --rank <n>               cycle through generators 1 to n only
+      --engine walk|rules      search with Walk, or with the rewrite rules
+      --max-depth <n>          look at words of up to n letters
+      --coefficients <ring>    Z, Q, Z/p or mod, Z by default
+      --threads <n>            threads to search on, all cores by default
+      --checkpoint <file>      save a walk search every so often
+      --every <seconds>        how often, 60 seconds by default
+      --resume <file>          go on with a saved walk search
+  check-rules <file>         check a rule file for confluence and termination
+      --complete <rounds>      run completion for a number of rounds
+      --strict                 fail too when a rule cannot be oriented
+  verify-derivation <file>   check every step of a derivation like those in docs
+  check-certificate <file>   replay a certificate written by simplify
+  script <file>              run a script of lets, loops and stops
+  repl                       simplify expressions typed in
+  bench                      time Walk and the rules on the same words
+      --length <n>             letters in each word, 8 by default
+
+Options for every command:
+  --cartan <matrix>          a Dynkin type such as A3 or a matrix file,
+                             ./src/matrix by default
+  --rules <file>             the rule file, ./src/map by default
+  --sections <a,b,...>       only the rules of these sections of the rule
+                             file, after the Serre relations
+
+Without a command, `proof [matrix] [coefficients] [words]` searches.
+
+Exit codes: 0 when everything checks out, 1 when a check fails, 2 for a bad
+command line, 3 when a file cannot be read and 4 when a search finds a
+candidate.";
+
+/// Prints what was wrong with the command line and exits.
+fn usage(reason: &str) -> ! {
+    eprintln!("{reason}\n\n{USAGE}");
+    std::process::exit(exit::USAGE);
+}
 
-use std::collections::HashMap;
+/// Removes `--name value` from the arguments, returning the value.
+fn take_flag(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let i = args.iter().position(|arg| arg == name)?;
 
-use parser::AST;
-use value::*;
+    if i + 1 >= args.len() {
+        usage(&format!("Missing value for {name}"));
+    }
 
-pub struct Scope(HashMap<String, Value>);
+    let value = args.remove(i + 1);
+    args.remove(i);
 
-impl Scope {
-    fn set(&mut self, name: &str, value: Value) {
-        self.0.insert(name.to_string(), value);
+    return Some(value);
+}
+
+/// `take_flag`, parsed.
+fn parse_flag<T: std::str::FromStr>(args: &mut Vec<String>, name: &str) -> Option<T> {
+    let arg = take_flag(args, name)?;
+    return Some(arg.parse().unwrap_or_else(|_| usage(&format!("Unknown value {arg} for {name}"))));
+}
+
+/// Removes `--name` from the arguments, returning whether it was there.
+fn take_switch(args: &mut Vec<String>, name: &str) -> bool {
+    let Some(i) = args.iter().position(|arg| arg == name) else {
+        return false;
+    };
+
+    args.remove(i);
+    return true;
+}
+
+/// The one argument left after the options, or a usage error naming it.
+fn take_argument(args: &[String], what: &str) -> String {
+    if let Some(flag) = args.iter().find(|arg| arg.starts_with("--")) {
+        usage(&format!("Unknown option {flag}"));
     }
 
-    fn get(&self, name: &str) -> Option<Value> {
-        return self.0.get(name).cloned();
+    match args {
+        [arg] => return arg.clone(),
+        [] => usage(&format!("Missing {what}")),
+        [_, extra, ..] => usage(&format!("Unexpected argument {extra}")),
     }
+}
 
-    fn get_number(&self, name: &str) -> i32 {
-        let value = self.get(name).expect(&format!("Could not find variable {name}!"));
-    
-        if let Value::Number(num) = value {
-            return num
-        } else {
-            panic!("Expected a number for {name}, got {value}!")
-        }
+/// Where the library's commands write what they find as they go.
+fn print(line: String) {
+    println!("{line}");
+}
+
+fn no_arguments(args: &[String]) {
+    if let Some(arg) = args.first() {
+        usage(&format!("Unexpected argument {arg}"));
     }
 }
 
-fn ast_match(pattern: &AST, value: &Value, scope: &mut Scope) -> bool {
-    match pattern {
-        AST::Named(name) => {
-            if let Some(current) = scope.get(&name) {
-                return value == &current;
-            } else {
-                scope.set(name, value.clone());
-                return true;
-            }
-        }
-        AST::Value(current) => return current == value,
-        AST::Braket(a, b) => {
-            if let Value::Braket(va, vb) = value {
-                return ast_match(a, va.as_ref(), scope) && ast_match(b, vb.as_ref(), scope);
-            } else {
-                return false;
-            }
-        }
-        AST::Kind(name, avalue) => {
-            if let Value::Kind(vname, vvalue) = value {
-                if name == vname {
-                    if let Some(avalue) = scope.get(avalue) {
-                        return avalue == Value::Number(*vvalue); 
-                    }
-
-                    scope.set(avalue, Value::Number(*vvalue));
-                    return true;
-                }
-            }
-            return false;
-        }
-        AST::Negative(pattern) => {
-            if let Value::Negative(value) = value {
-                return ast_match(pattern, value, scope);
-            }
+/// The rules at `path` after the Serre relations of `cartan`, only those
+/// of `sections` if given.
+fn load_rules(path: &str, sections: &Option<Vec<String>>, cartan: &CartanMatrix) -> Result<RuleSet, ProofError> {
+    match sections {
+        Some(sections) => return RuleSet::load_sections(path, &sections.iter().map(String::as_str).collect::<Vec<&str>>(), cartan),
+        None => return RuleSet::load(path, cartan),
+    }
+}
 
-            return false;
-        }
-        AST::Add(a, b) => {
-            if let Value::Add(va, vb) = value {
-                return ast_match(a, va, scope) && ast_match(b, vb, scope);
-            }
-            return false;
-        }
-        AST::Mul(a, b) => {
-            if let Value::Mul(va, vb) = value {
-                return ast_match(a, va, scope) && ast_match(b, vb, scope);
-            }
-            return false;
-        }
-        AST::C(_, _) => {
-            panic!("C statments not allowed on left side of equal!")
+/// Searches, or goes on with a saved search. The matrix, coefficients and
+/// words are the positional `legacy` arguments when there are any, so that
+/// `proof A3 Z all:8` still works.
+fn search_command(args: &mut Vec<String>, cartan: Option<CartanMatrix>, rules: &str, sections: &Option<Vec<String>>, legacy: bool) -> Result<i32, ProofError> {
+    // All cores unless told otherwise.
+    let threads: Option<usize> = parse_flag(args, "--threads");
+
+    // Save the search to a file every so often, 60 seconds by default.
+    let every: Option<u64> = parse_flag(args, "--every");
+    let checkpoint = take_flag(args, "--checkpoint");
+    let max_depth = parse_flag(args, "--max-depth");
+
+    let engine = take_flag(args, "--engine").unwrap_or("walk".to_string());
+    let rank: Option<i32> = parse_flag(args, "--rank");
+    let word = take_flag(args, "--word");
+    let coefficients = parse_flag(args, "--coefficients");
+
+    // The rules engine runs on one thread over the integers, and cannot be
+    // saved or resumed: it is a check on Walk rather than a search to run
+    // for days, and a checkpoint would have to carry the rule file too.
+    if engine == "rules" {
+        let walk_only = [
+            ("--threads", threads.is_some()),
+            ("--checkpoint", checkpoint.is_some()),
+            ("--every", every.is_some()),
+            ("--resume", args.iter().any(|arg| arg == "--resume")),
+            ("--coefficients", coefficients.is_some()),
+        ];
+
+        if let Some((flag, _)) = walk_only.iter().find(|(_, given)| *given) {
+            usage(&format!("{flag} only applies to --engine walk"));
         }
     }
-}
 
-const ARR: [[i32; 3]; 3] = [
-    [  2, -1, -1 ],
-    [ -1,  2, -2 ],
-    [ -1, -1,  2 ]
-];
+    let threads = threads.unwrap_or(0);
+    let every = Duration::from_secs(every.unwrap_or(60));
+
+    let found = |found: u64| if found > 0 { exit::FOUND } else { exit::OK };
 
-fn ast_number(expresion: &AST, scope: &Scope) -> usize {
-    let value = ast_build(expresion, scope);
+    // Pick up a saved search, and keep saving to the same file unless told
+    // otherwise.
+    if let Some(path) = take_flag(args, "--resume") {
+        no_arguments(args);
 
-    if let Value::Number(num) = value {
-        return num as usize;
+        let options = SearchOptions { threads, checkpoint: Some((checkpoint.unwrap_or(path.clone()), every)), max_depth };
+        return Ok(found(driver::resume(&path, &options, &mut print)?));
     }
 
-    panic!("Failed to get number for ast_number!");
-}
+    let options = SearchOptions { threads, checkpoint: checkpoint.map(|path| (path, every)), max_depth };
 
-fn ast_build(expresion: &AST, scope: &Scope) -> Value {
-    match expresion {
-        AST::Named(name) => scope.get(&name).expect(&format!("Could not find variable {name}!")),
-        AST::Value(value) => value.clone(),
-        AST::Kind(name, value) => Value::Kind(name.clone(), scope.get_number(&value)),
-        AST::Negative(expresion) => Value::Negative(Box::new(ast_build(expresion, scope))),
-        AST::Braket(a, b) => Value::Braket(
-            Box::new(ast_build(a, scope)),
-            Box::new(ast_build(b, scope))
-        ),
-        AST::Add(a, b) => Value::Add(
-            Box::new(ast_build(a, scope)),
-            Box::new(ast_build(b, scope))
-        ),
-        AST::Mul(a, b) => Value::Mul(
-            Box::new(ast_build(a, scope)),
-            Box::new(ast_build(b, scope))
-        ),
-        AST::C(a, b) => {
-            let value = ARR[ast_number(a, scope) - 1][ast_number(b, scope) - 1];
-            if value >= 0 {
-                Value::Number(value)
-            } else {
-                Value::Negative(Box::new(Value::Number(-value)))
-            }
-        }
+    if let Some(flag) = args.iter().find(|arg| arg.starts_with("--")) {
+        usage(&format!("Unknown option {flag}"));
     }
-}
 
-fn ast_match_and_build(value: Value, patterns: &Vec<(AST, AST)>) -> Value {
-    for (pattern, expresion) in patterns {
-        let scope = &mut Scope(HashMap::new());
+    if !legacy {
+        no_arguments(args);
+    }
 
-        if ast_match(&pattern, &value, scope) {
-            return simplify(ast_build(expresion, scope), patterns);
-        }
+    if !args.is_empty() && (cartan.is_some() || coefficients.is_some() || word.is_some()) {
+        usage("Give the matrix, coefficients and words either in order or as options, not both");
+    }
 
-        if let Some(pattern) = pattern.flip() {
-            if ast_match(&pattern, &value, scope) {
-                return simplify(ast_build(&expresion.negate(), scope), patterns);
-            }
-        }
+    // The matrix can be given as a Dynkin type (A3, E8, ...) or a matrix file.
+    let cartan = match (cartan, args.first()) {
+        (Some(cartan), _) => cartan,
+        (None, Some(arg)) => CartanMatrix::from_arg(arg)?,
+        (None, None) => CartanMatrix::load("./src/matrix")?,
+    };
+
+    // Coefficients are Z, Q, Z/p for a prime p, or mod for a large default prime.
+    let coefficients = match (coefficients, args.get(1)) {
+        (Some(coefficients), _) => coefficients,
+        (None, Some(arg)) => arg.parse().unwrap_or_else(|_| usage(&format!("Unknown coefficient ring {arg}"))),
+        (None, None) => Coefficients::Integers,
+    };
+
+    print!("{}", cartan.report());
+
+    if !cartan.validate().is_empty() {
+        return Ok(exit::INPUT);
     }
 
-    return value;
-}
+    // Words to search are word:1,2,1,3, cycle:1,2,3, all:8 or bfs:8, by
+    // default every generator in turn. The max depth only bounds them.
+    let words = match (word.or(args.get(2).cloned()), rank) {
+        (Some(arg), _) => match search::read_word(&arg) {
+            Some(word) => Search::Word(word),
+            None => arg.parse().unwrap_or_else(|_| usage(&format!("Unknown search {arg}"))),
+        },
+        (None, Some(rank)) if rank < 1 => usage(&format!("Unknown rank {rank}")),
+        (None, Some(rank)) => Search::Periodic((1..=rank).collect()),
+        (None, None) => Search::cycle(&cartan),
+    };
+
+    if let Some(e) = words.letters().iter().find(|e| **e < 1 || **e as usize > cartan.rank()) {
+        usage(&format!("E({e}) is not a generator of a rank {} matrix", cartan.rank()));
+    }
 
-fn simplify(value: Value, patterns: &Vec<(AST, AST)>) -> Value {
-    match value {
-        Value::Add(a, b) => {
-            return ast_match_and_build(Value::Add(Box::new(simplify(*a, patterns)), Box::new(simplify(*b, patterns))), patterns)
-        }
-        Value::Negative(value) => {
-            return ast_match_and_build(Value::Negative(Box::new(simplify(*value, patterns))), patterns)
-        }
-        Value::Mul(a, b) => {    
-            if let Some(a) = a.into_number() {
-                if let Some(b) = b.into_number() {
-                    return number(a * b);
-                }
+    match engine.as_str() {
+        "walk" => return Ok(found(driver::search(&words, &cartan, coefficients, &options, &mut print)?)),
+        "rules" => {
+            if coefficients != Coefficients::Integers {
+                usage(&format!("Coefficients {coefficients} only apply to --engine walk"));
             }
 
-            return ast_match_and_build(Value::Mul(Box::new(simplify(*a, patterns)), Box::new(simplify(*b, patterns))), patterns)
-        }
-        Value::Braket(a, b) => {
-            return ast_match_and_build(Value::Braket(Box::new(simplify(*a, patterns)), Box::new(simplify(*b, patterns))), patterns)
+            let cartan = Arc::new(cartan);
+            let rules = load_rules(rules, sections, &cartan)?;
+
+            return Ok(found(driver::search_with_rules(&words, &rules, &cartan, max_depth.unwrap_or(9), &mut print)?));
         }
-        Value::Number(_) => return value,
-        Value::Kind(_, _) => return value,
+        _ => usage(&format!("Unknown engine {engine}")),
     }
 }
 
-fn standerdize(value: Value) -> Value {
-    match value {
-        Value::Braket(a, b) => {
-            match (a.as_ref(), b.as_ref()) {
-                (Value::Kind(_, na), Value::Kind(_, nb)) => {
-                    if na > nb {
-                        Value::Braket(a, b)
-                    } else {
-                        Value::Negative(Box::new(Value::Braket(b, a)))
-                    }
-                }
-                (k @ Value::Kind(_, _),  b @ Value::Braket(_, _)) => {
-                    match standerdize(b.clone()) {
-                        Value::Negative(b) => Value::Braket(Box::new(standerdize(*b)), Box::new(k.clone())),
-                        b => Value::Negative(Box::new(Value::Braket(Box::new(standerdize(b.clone())), Box::new(k.clone())))),
-                    }
-                }
-                (b @ Value::Braket(_, _), k @ Value::Kind(_, _)) => {
-                    match standerdize(b.clone()) {
-                        Value::Negative(b) => Value::Negative(Box::new(Value::Braket(Box::new(standerdize(*b)), Box::new(k.clone())))),
-                        b => Value::Braket(Box::new(standerdize(b.clone())), Box::new(k.clone())),
-                    }
-                }
-                _ => Value::Braket(a, b)
-            }
-        }
-        _ => value
+fn run(command: &str, mut args: Vec<String>) -> Result<i32, ProofError> {
+    let args = &mut args;
+
+    let rules = take_flag(args, "--rules").unwrap_or("./src/map".to_string());
+    let sections = take_flag(args, "--sections").map(|arg| arg.split(',').map(|name| name.trim().to_string()).collect::<Vec<String>>());
+    let cartan = take_flag(args, "--cartan").map(|arg| CartanMatrix::from_arg(&arg)).transpose()?;
+
+    if command == "search" || command == "legacy" {
+        return search_command(args, cartan, &rules, &sections, command == "legacy");
     }
-}
 
-fn is_lots_of_es_zero(mut value: Value) -> Value {
-    // println!("s: {value}");
-    let mut es: Vec<(i32, Value)> = vec! [];
-
-    loop {
-        match value {
-            Value::Add(a, b) => {
-                match *a {
-                    Value::Negative(ref value) => {
-                        match *value.clone() {
-                            Value::Mul(n, e) => es.push((-n.into_number().expect("expected number"), *e.clone())),
-                            v => es.push((-1, v)),
-                        }
-                    }
-                    Value::Mul(n, e) => es.push((n.into_number().expect("expected number"), *e)),
-                    value => es.push((1, value)),
+    let cartan = match cartan {
+        Some(cartan) => cartan,
+        None => CartanMatrix::load("./src/matrix")?,
+    };
+
+    let invalid = !cartan.validate().is_empty();
+
+    if command != "simplify" || invalid {
+        print!("{}", cartan.report());
+    }
+
+    if invalid {
+        return Ok(exit::INPUT);
+    }
+
+    let cartan = Arc::new(cartan);
+    let passed = |passed: bool| if passed { exit::OK } else { exit::FAILED };
+    let verdict = |verdict: Verdict| {
+        print!("{verdict}");
+        return passed(verdict.holds);
+    };
+
+    match command {
+        "simplify" => {
+            let show_trace = take_switch(args, "--trace");
+            let derivation = take_flag(args, "--derivation");
+            let certificate = take_flag(args, "--certificate");
+            let src = take_argument(args, "expression");
+
+            let rules = load_rules(&rules, &sections, &cartan)?;
+            let scope = Scope::new(&cartan);
+
+            let start = match script::build(&src, &scope) {
+                Ok(start) => start,
+                Err(reason) => {
+                    eprintln!("{reason}");
+                    return Ok(exit::INPUT);
                 }
-                value = *b;
-            }
-            Value::Mul(ref n, ref e) => {
-                es.push((n.clone().into_number().expect("expected number"), *e.clone()));
-                break;
+            };
+
+            let trace = proof::simplify_traced(start, &rules, &scope)?;
+
+            if show_trace {
+                print!("{trace}");
+            } else {
+                println!("{}", AST::from_value(&trace.result));
             }
-            Value::Negative(ref value) => {
-                match *value.clone() {
-                    Value::Mul(n, e) => es.push((-n.into_number().expect("expected number"), *e.clone())),
-                    v => es.push((-1, v)),
-                }
-                break;
+
+            if let Some(path) = derivation {
+                std::fs::write(&path, trace::derivations(std::slice::from_ref(&trace))?)
+                    .map_err(|source| ProofError::Io { path: path.clone(), source })?;
             }
-            value => {
-                es.push((1, value));
-                break;
+
+            if let Some(path) = certificate {
+                std::fs::write(&path, trace.to_string()).map_err(|source| ProofError::Io { path: path.clone(), source })?;
             }
+
+            return Ok(exit::OK);
         }
-    }
+        "check-rules" => {
+            let rounds = parse_flag(args, "--complete");
+            let strict = take_switch(args, "--strict");
+            let path = take_argument(args, "rule file");
+
+            let rules = load_rules(&path, &sections, &cartan)?;
+            let confluence = confluence::report(&rules, &cartan, rounds)?;
+            let termination = termination::report(&rules);
+            print!("{confluence}{termination}");
+
+            // Rules the ordering cannot orient are only warned about, as
+            // rewriting still stops on its budget.
+            return Ok(passed(confluence.holds && (termination.terminates() || !strict)));
+        }
+        "verify-derivation" => {
+            let path = take_argument(args, "derivation file");
+            return Ok(verdict(derivation::report(&path, &load_rules(&rules, &sections, &cartan)?, &cartan)?));
+        }
+        "check-certificate" => {
+            let path = take_argument(args, "certificate");
+            return Ok(verdict(certificate::report(&path, &load_rules(&rules, &sections, &cartan)?, &cartan)?));
+        }
+        "script" => {
+            let path = take_argument(args, "script");
+            return Ok(verdict(script::report(&path, &load_rules(&rules, &sections, &cartan)?, &cartan, &mut print)?));
+        }
+        "repl" => {
+            let coefficients = parse_flag(args, "--coefficients").unwrap_or(Coefficients::Integers);
+            no_arguments(args);
 
-    es = es.into_iter().map(|(n, e)| match standerdize(e) {
-        Value::Negative(e) => (-n, *e),
-        e => (n, e),
-    }).collect();
-
-    for i in (1..es.len()).rev() {
-        for j in 0..i {
-            if es[i].1 == es[j].1 {
-                es[j].0 += es[i].0;
-                es.remove(i);
-                break;
-            }
+            repl::run(&rules, sections, Scope::new(&cartan).with_coefficients(coefficients))?;
+            return Ok(exit::OK);
         }
-    }
+        "bench" => {
+            let length = parse_flag(args, "--length").unwrap_or(8);
+            no_arguments(args);
 
-    // for (i, e) in &es {
-    //     println!("-> {i} * {e}")
-    // }
-
-    return es.into_iter()
-        .filter(|(n, _)| n != &0)
-        .map(|(n, e)| if n == 1 {
-            e
-        } else if n == -1 {
-            Value::Negative(Box::new(e))
-        } else if n > 0 {
-            Value::Mul(Box::new(Value::Number(n)), Box::new(e))
-        } else {
-            Value::Negative(Box::new(Value::Mul(Box::new(Value::Number(-n)), Box::new(e))))
-        })
-        .reduce(|p, v| Value::Add(Box::new(p), Box::new(v)))
-        .unwrap_or(Value::zero());
+            return Ok(verdict(driver::bench(length, &load_rules(&rules, &sections, &cartan)?, &cartan)?));
+        }
+        _ => unreachable!(),
+    }
 }
 
-pub fn str_build(str: &str) -> Value {
-    return ast_build(&parser::parse(str), &Scope(HashMap::new()));
-}
+/// The error and what caused it, on one line.
+fn describe(err: &ProofError) -> String {
+    let mut out = err.to_string();
+    let mut source = err.source();
 
-pub fn check_conter_example_from_string(str: &str) {
-    let ast = str_build(str);
-    let patterns = &parser::load("./src/map");
-    let value = is_lots_of_es_zero(simplify(ast, patterns));
-    println!("{value}");
-}
+    while let Some(cause) = source {
+        out += &format!(": {cause}");
+        source = cause.source();
+    }
 
-pub fn make(src: &str, patterns: &Vec<(AST, AST)>, scope: &Scope) -> Value {
-    is_lots_of_es_zero(simplify(ast_build(&parser::parse(src), scope), patterns))
+    return out;
 }
 
-pub fn n_f(n: i32, b: i32) -> Value {
-    let patterns = &parser::load("./src/map");
-
-    let mut scope = Scope(HashMap::new());
-    scope.0.insert("b".to_string(), Value::Number(b));
+const COMMANDS: [&str; 8] = ["simplify", "search", "check-rules", "verify-derivation", "check-certificate", "script", "repl", "bench"];
 
-    let mut nx   = make("[E(1), E(2)]", patterns, &scope);
-    let mut nx_f = make("[[E(1), E(2)], F(b)]", patterns, &scope);
-    let mut nx_h = make("[[E(1), E(2)], H(b)]", patterns, &scope);
+/// The options that take no value.
+const SWITCHES: [&str; 1] = ["--trace"];
 
-    for n in 2..n {
-        scope.0.insert("n".to_string(), Value::Number(n % 3 + 1));
-        scope.0.insert("nx".to_string(), nx.clone());
-        scope.0.insert("nx_f".to_string(), nx_f);
-        scope.0.insert("nx_h".to_string(), nx_h);
+fn main() {
+    let mut args = std::env::args().skip(1).collect::<Vec<String>>();
 
-        nx = brak(nx, e(n % 3 + 1)); 
+    if args.first().is_some_and(|arg| arg == "help" || arg == "--help") {
+        println!("{USAGE}");
+        return;
+    }
 
-        nx_f = if n & 3 + 1 == b {
-            make("[nx_f, E(n)] - nx_h ", patterns, &scope)
-        } else {
-            make("[nx_f, E(n)]", patterns, &scope)
-        };
+    // Options can come before the command too, so look past them for it.
+    let mut i = 0;
 
-        nx_h = make(
-            "[nx_h, E(n)] - C(b, n) * [nx, E(n)]",
-            patterns, &scope
-        ); 
+    while args.get(i).is_some_and(|arg| arg.starts_with("--")) {
+        i += if SWITCHES.contains(&args[i].as_str()) { 1 } else { 2 };
     }
 
-    return nx_f;
-}
-
-pub fn find_conter_exmaple(n: i32) -> bool {
-    let patterns = &parser::load("./src/map");
-
-    let mut scope = Scope(HashMap::new());
-
-    let mut nx   = make("[E(1), E(2)]", patterns, &scope);
-    let mut nx_f1 = make("[[E(1), E(2)], F(1)]", patterns, &scope);
-    let mut nx_h1 = make("[[E(1), E(2)], H(1)]", patterns, &scope);
-    let mut nx_f2 = make("[[E(1), E(2)], F(2)]", patterns, &scope);
-    let mut nx_h2 = make("[[E(1), E(2)], H(2)]", patterns, &scope);
-    let mut nx_f3 = make("[[E(1), E(2)], F(3)]", patterns, &scope);
-    let mut nx_h3 = make("[[E(1), E(2)], H(3)]", patterns, &scope);
-
-    for n in 2..n {
-        println!("Checking {}", n);
-        scope.0.insert("n".to_string(), Value::Number(n % 3 + 1));
-        scope.0.insert("nx".to_string(), nx.clone());
-        scope.0.insert("nx_f1".to_string(), nx_f1);
-        scope.0.insert("nx_f2".to_string(), nx_f2);
-        scope.0.insert("nx_f3".to_string(), nx_f3);
-        scope.0.insert("nx_h1".to_string(), nx_h1);
-        scope.0.insert("nx_h2".to_string(), nx_h2);
-        scope.0.insert("nx_h3".to_string(), nx_h3);
-
-        nx = brak(nx, e(n % 3 + 1)); 
-
-        nx_f1 = if n & 3 + 1 == 1 {
-            make("[nx_f1, E(n)] - nx_h1", patterns, &scope)
-        } else {
-            make("[nx_f1, E(n)]", patterns, &scope)
-        };
-
-        nx_f2 = if n & 3 + 1 == 2 {
-            make("[nx_f2, E(n)] - nx_h2", patterns, &scope)
-        } else {
-            make("[nx_f2, E(n)]", patterns, &scope)
-        };
-
-        nx_f3 = if n & 3 + 1 == 3 {
-            make("[nx_f3, E(n)] - nx_h3", patterns, &scope)
-        } else {
-            make("[nx_f3, E(n)]", patterns, &scope)
-        };
-
-        nx_h1 = make("[nx_h1, E(n)] - C(1, n) * [nx, E(n)]", patterns, &scope); 
-        nx_h2 = make("[nx_h2, E(n)] - C(2, n) * [nx, E(n)]", patterns, &scope); 
-        nx_h3 = make("[nx_h3, E(n)] - C(3, n) * [nx, E(n)]", patterns, &scope); 
-
-        if nx_f1 == Value::zero() && nx_f2 == Value::zero() && nx_f3 == Value::zero()  {
-            println!("ZERO!!! @ {}", n);
-            return true;
+    let command = match args.get(i) {
+        Some(arg) if COMMANDS.contains(&arg.as_str()) => args.remove(i),
+        // Otherwise the first argument is a matrix, if there is one.
+        Some(arg) if arg.parse::<CartanMatrix>().is_err() && std::fs::metadata(arg).is_err() => {
+            usage(&format!("Unknown command {arg}"));
+        }
+        _ => "legacy".to_string(),
+    };
+
+    match run(&command, args) {
+        Ok(code) => std::process::exit(code),
+        // Rules that loop are rules that fail a check; anything else is
+        // input that could not be read.
+        Err(err @ ProofError::Rewrite(_)) => {
+            eprintln!("{err}");
+            std::process::exit(exit::FAILED);
+        }
+        Err(err) => {
+            eprintln!("{}", describe(&err));
+            std::process::exit(exit::INPUT);
         }
     }
-
-    return false;
 }
-
-fn main() {
-    v2::find_counter_example();
-
-    let patterns = &parser::load("./src/map");
-    let scope = &Scope(HashMap::new());
-    // println!("\nn4_h1 = {}", make("[[[[E(1), E(2)], E(3)], E(1)], H(1)]", patterns, scope));
-}
\ No newline at end of file
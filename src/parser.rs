@@ -1,3 +1,7 @@
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use crate::integer::Integer;
 use crate::value::Value;
 
 #[derive(Clone, Copy, Debug)]
@@ -24,10 +28,22 @@ impl<'a> Parser<'a> {
         return None;
     }
 
+    fn peek(&self) -> Option<char> {
+        return self.src[self.index..].chars().next();
+    }
+
     fn starts_with_char(&self, chr: char) -> Option<Self> {
         return self.starts_with(|c| c == chr);
     }
 
+    fn expect_char(&self, chr: char) -> Result<Self, Failure> {
+        return self.starts_with_char(chr).ok_or(self.fail(Expected::Char(chr)));
+    }
+
+    fn fail(&self, expected: Expected) -> Failure {
+        return Failure { index: self.index, expected };
+    }
+
     fn next_while(&self, func: impl Fn(char) -> bool) -> Self {
         for (i, chr) in self.src[self.index..].char_indices() {
             if !func(chr) {
@@ -45,6 +61,80 @@ impl<'a> Parser<'a> {
 
 ///////
 
+/// What the parser wanted to see where it stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Expected {
+    Char(char),
+    Value,
+    Name,
+    Number,
+    End,
+    Comparison,
+    File,
+    NotIncluded,
+}
+
+impl Display for Expected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expected::Char(chr) => write!(f, "'{chr}'"),
+            Expected::Value => write!(f, "a value"),
+            Expected::Name => write!(f, "a name"),
+            Expected::Number => write!(f, "a number"),
+            Expected::End => write!(f, "end of input"),
+            Expected::Comparison => write!(f, "'==' or '!='"),
+            Expected::File => write!(f, "a readable rule file"),
+            Expected::NotIncluded => write!(f, "a file that is not already being loaded"),
+        }
+    }
+}
+
+/// A failure at a byte offset into the source being parsed.
+#[derive(Clone, Copy, Debug)]
+struct Failure {
+    index: usize,
+    expected: Expected,
+}
+
+type Parsed<'a, T> = Result<(Parser<'a>, T), Failure>;
+
+/// A parse failure, located in the file it came from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub file: String,
+    /// One based, like an editor.
+    pub line: usize,
+    /// One based and counted in characters.
+    pub column: usize,
+    pub expected: Expected,
+    /// The text of the offending line, for the caret.
+    pub text: String,
+}
+
+impl ParseError {
+    /// Locates a failure at byte `index` of `text`, which is line `line` of
+    /// `file`.
+    fn new(file: &str, line: usize, text: &str, index: usize, expected: Expected) -> ParseError {
+        return ParseError {
+            file: file.to_string(),
+            line,
+            column: text[..index].chars().count() + 1,
+            expected,
+            text: text.to_string(),
+        };
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}:{}:{}: expected {}", self.file, self.line, self.column, self.expected)?;
+        writeln!(f, "    {}", self.text)?;
+        return write!(f, "    {}^", " ".repeat(self.column - 1));
+    }
+}
+
+///////
+
 #[derive(Debug, Clone)]
 pub enum AST {
     Named(String),
@@ -69,162 +159,752 @@ impl AST {
     pub fn negate(&self) -> Self {
         return AST::Negative(Box::new(self.clone()));
     }
+
+    /// Whether `C(a, b)` appears anywhere in the tree.
+    pub fn has_c(&self) -> bool {
+        match self {
+            AST::C(_, _) => return true,
+            AST::Braket(a, b) | AST::Add(a, b) | AST::Mul(a, b) => return a.has_c() || b.has_c(),
+            AST::Negative(a) => return a.has_c(),
+            AST::Named(_) | AST::Value(_) | AST::Kind(_, _) => return false,
+        }
+    }
+
+    /// The tree of a value, with numbers and generators as leaves.
+    pub fn from_value(value: &Value) -> AST {
+        match value {
+            Value::Braket(a, b) => return AST::Braket(Box::new(AST::from_value(a)), Box::new(AST::from_value(b))),
+            Value::Add(a, b) => return AST::Add(Box::new(AST::from_value(a)), Box::new(AST::from_value(b))),
+            Value::Mul(a, b) => return AST::Mul(Box::new(AST::from_value(a)), Box::new(AST::from_value(b))),
+            Value::Negative(a) => return AST::Negative(Box::new(AST::from_value(a))),
+            Value::Number(_) | Value::Kind(_, _) => return AST::Value(value.clone()),
+        }
+    }
+
+    /// How tightly the tree binds when written out: sums, then negations,
+    /// then products, then everything that is never bracketed.
+    fn binding(&self) -> u8 {
+        match self {
+            AST::Add(_, _) => return 1,
+            AST::Negative(_) => return 2,
+            AST::Mul(_, _) => return 3,
+            AST::Value(Value::Number(num)) if num.is_negative() => return 2,
+            AST::Value(Value::Number(_) | Value::Kind(_, _)) => return 4,
+            AST::Value(value) => return AST::from_value(value).binding(),
+            _ => return 4,
+        }
+    }
+
+    /// Writes the tree with just the parentheses it needs to parse back to
+    /// the same tree, at a place that only takes trees binding at least as
+    /// tightly as `min`.
+    fn write(&self, f: &mut std::fmt::Formatter<'_>, min: u8) -> std::fmt::Result {
+        if self.binding() < min {
+            write!(f, "(")?;
+            self.write(f, 1)?;
+            return write!(f, ")");
+        }
+
+        match self {
+            AST::Named(name) => return write!(f, "{name}"),
+            AST::Value(Value::Number(num)) if num.is_negative() => return write!(f, "-{}", -num.clone()),
+            AST::Value(Value::Number(num)) => return write!(f, "{num}"),
+            AST::Value(Value::Kind(name, n)) => return write!(f, "{name}({n})"),
+            AST::Value(value) => return AST::from_value(value).write(f, min),
+            AST::Kind(name, value) => return write!(f, "{name}({value})"),
+            AST::Braket(a, b) => return write!(f, "[{a}, {b}]"),
+            AST::C(a, b) => return write!(f, "C({a}, {b})"),
+            AST::Negative(a) => {
+                write!(f, "-")?;
+                return a.write(f, 2);
+            }
+            AST::Add(a, b) => {
+                a.write(f, 1)?;
+
+                // `a - b` parses to `a + (-b)`.
+                if let AST::Negative(b) = b.as_ref() {
+                    write!(f, " - ")?;
+                    return b.write(f, 2);
+                }
+
+                write!(f, " + ")?;
+                return b.write(f, 2);
+            }
+            AST::Mul(a, b) => {
+                a.write(f, 3)?;
+                write!(f, " * ")?;
+                return b.write(f, 4);
+            }
+        }
+    }
+}
+
+impl Display for AST {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return self.write(f, 1);
+    }
 }
 
 ///////
 
-fn parse_word(src: Parser) -> Option<(Parser, String)> {
+fn parse_word(src: Parser) -> Parsed<String> {
     let start = src.index;
 
-    let src = src.starts_with(|chr| chr.is_ascii_alphabetic())?;
+    let src = src.starts_with(|chr| chr.is_ascii_alphabetic()).ok_or(src.fail(Expected::Name))?;
     let src = src.next_while(|chr| chr.is_ascii_alphanumeric() || chr == '_');
 
     let end = src.index;
 
-    return Some((src, src.src[start..end].to_string()));
+    return Ok((src, src.src[start..end].to_string()));
 }
 
-fn parse_c(src: Parser) -> Option<(Parser, AST)> {
-    let src = src.starts_with_char('C')?;
-    let src = src.starts_with_char('(')?;
+fn parse_c(src: Parser) -> Parsed<AST> {
+    let src = src.expect_char('C')?;
+    let src = src.expect_char('(')?;
     let src = src.skip_whitespace();
     let (src, a) = parse_value(src)?;
-    let src = src.starts_with_char(',')?;
+    let src = src.expect_char(',')?;
     let src = src.skip_whitespace();
     let (src, b) = parse_value(src)?;
-    let src = src.starts_with_char(')')?;
+    let src = src.expect_char(')')?;
 
-    return Some((src, AST::C(Box::new(a), Box::new(b))));
+    return Ok((src, AST::C(Box::new(a), Box::new(b))));
 }
 
-fn parse_named(src: Parser) -> Option<(Parser, AST)> {
+fn parse_named(src: Parser) -> Parsed<AST> {
     let (src, name) = parse_word(src)?;
 
     if let Some(src) = src.starts_with_char('(') {
-        if let Some((src, value)) = parse_word(src) {
-            let src = src.starts_with_char(')')?;
+        if let Ok((src, value)) = parse_word(src) {
+            let src = src.expect_char(')')?;
 
-            return Some((src, AST::Kind(name, value)));
+            return Ok((src, AST::Kind(name, value)));
         }
 
-        if let Some((src, value)) = parse_number(src) {
-            let src = src.starts_with_char(')')?;
+        if let Ok((after, value)) = parse_digits(src) {
+            let value = value.parse::<i32>().map_err(|_| src.fail(Expected::Number))?;
+            let src = after.expect_char(')')?;
 
-            return Some((src, AST::Value(Value::Kind(name, value))));
+            return Ok((src, AST::Value(Value::Kind(name, value))));
         }
     }
 
-    return Some((src, AST::Named(name)));
+    return Ok((src, AST::Named(name)));
 }
 
-fn parse_number(src: Parser) -> Option<(Parser, i32)> {
+fn parse_digits(src: Parser<'_>) -> Parsed<'_, &str> {
     let start = src.index;
-    let src = src.next_while(|chr| chr.is_numeric());
+    let src = src.next_while(|chr| chr.is_ascii_digit());
     let end = src.index;
 
     if start == end {
-        return None;
+        return Err(src.fail(Expected::Number));
     }
 
-    return Some((src, src.src[start..end].parse::<i32>().expect("Failed to parse number!")));
+    return Ok((src, &src.src[start..end]));
 }
 
-fn parse_number_value(src: Parser) -> Option<(Parser, AST)> {
-    let (src, num) = parse_number(src)?;
-    return Some((src, AST::Value(Value::Number(num))));
+fn parse_number_value(src: Parser) -> Parsed<AST> {
+    let (src, num) = parse_digits(src)?;
+    let num = num.parse::<Integer>().expect("Digits always parse as an integer!");
+    return Ok((src, AST::Value(Value::Number(num))));
 }
 
-fn parse_braket(src: Parser) -> Option<(Parser, AST)> {
-    let src = src.starts_with_char('[')?;
+fn parse_braket(src: Parser) -> Parsed<AST> {
+    let src = src.expect_char('[')?;
     let src = src.skip_whitespace();
     
     let (src, a) = parse_value(src)?;
     
-    let src = src.starts_with_char(',')?;
+    let src = src.expect_char(',')?;
     let src = src.skip_whitespace();
     
     let (src, b) = parse_value(src)?;
 
-    let src = src.starts_with_char(']')?;
+    let src = src.expect_char(']')?;
     let src = src.skip_whitespace();
 
-    return Some((src, AST::Braket(Box::new(a), Box::new(b))));
+    return Ok((src, AST::Braket(Box::new(a), Box::new(b))));
 }
 
-fn parse_negative(src: Parser) -> Option<(Parser, AST)> {
-    let src = src.starts_with_char('-')?;
+fn parse_paren(src: Parser) -> Parsed<AST> {
+    let src = src.expect_char('(')?;
     let src = src.skip_whitespace();
     let (src, value) = parse_value(src)?;
-    return Some((src, AST::Negative(Box::new(value))));
+    let src = src.expect_char(')')?;
+    return Ok((src, value));
+}
+
+/// The first alternative that parses. Otherwise the failure that got
+/// furthest, since that is the one the author most likely meant.
+fn parse_atom(src: Parser) -> Parsed<AST> {
+    let parsers: [fn(Parser) -> Parsed<AST>; 5] = [
+        parse_paren,
+        parse_c,
+        parse_named,
+        parse_number_value,
+        parse_braket,
+    ];
+
+    let mut failure = src.fail(Expected::Value);
+
+    for parser in parsers {
+        match parser(src) {
+            Ok(parsed) => return Ok(parsed),
+            Err(err) if err.index > failure.index => failure = err,
+            Err(_) => {}
+        }
+    }
+
+    return Err(failure);
+}
+
+/// How tightly an infix operator binds, `*` tighter than `+` and `-`.
+fn precedence(chr: char) -> Option<u8> {
+    match chr {
+        '+' | '-' => return Some(1),
+        '*' => return Some(2),
+        _ => return None,
+    }
+}
+
+/// A leading `-` negates the product after it, so `-a * b` is `-(a * b)`
+/// and `-a + b` is `(-a) + b`.
+fn parse_unary(src: Parser) -> Parsed<AST> {
+    if let Some(src) = src.starts_with_char('-') {
+        let src = src.skip_whitespace();
+        let (src, value) = parse_binary(src, 2)?;
+        return Ok((src, AST::Negative(Box::new(value))));
+    }
+
+    return parse_atom(src);
 }
 
-fn parse_paren(src: Parser) -> Option<(Parser, AST)> {
-    let src = src.starts_with_char('(')?;
+/// Precedence climbing over operators binding at least as tightly as `min`.
+/// The right hand side only takes tighter operators, which makes every
+/// operator left associative: `a - b + c` is `(a - b) + c`.
+fn parse_binary(src: Parser, min: u8) -> Parsed<AST> {
+    let (src, mut a) = parse_unary(src)?;
+    let mut src = src.skip_whitespace();
+
+    while let Some(chr) = src.peek() {
+        let Some(prec) = precedence(chr).filter(|prec| *prec >= min) else {
+            break;
+        };
+
+        let after = src.set_index(1).skip_whitespace();
+        let (after, b) = parse_binary(after, prec + 1)?;
+
+        a = match chr {
+            '+' => AST::Add(Box::new(a), Box::new(b)),
+            '-' => AST::Add(Box::new(a), Box::new(AST::Negative(Box::new(b)))),
+            _ => AST::Mul(Box::new(a), Box::new(b)),
+        };
+
+        src = after.skip_whitespace();
+    }
+
+    return Ok((src, a));
+}
+
+fn parse_value(src: Parser) -> Parsed<AST> {
+    return parse_binary(src, 1);
+}
+
+/// Parses all of `src` as one expression, leading and trailing whitespace
+/// aside.
+fn parse_all(src: &str) -> Result<AST, Failure> {
+    let src = Parser { src, index: 0 }.skip_whitespace();
+    let (src, ast) = parse_value(src)?;
     let src = src.skip_whitespace();
-    let (src, value) = parse_value(src)?;
-    let src = src.starts_with_char(')')?;
-    return Some((src, value));
+
+    if src.index < src.src.len() {
+        return Err(src.fail(Expected::End));
+    }
+
+    return Ok(ast);
+}
+
+pub fn parse(src: &str) -> Result<AST, ParseError> {
+    return parse_all(src).map_err(|err| {
+        // Point into the line the failure is on.
+        let start = src[..err.index].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line = src[..start].matches('\n').count() + 1;
+        let text = src[start..].lines().next().unwrap_or("");
+
+        return ParseError::new("<input>", line, text, err.index - start, err.expected);
+    });
+}
+
+/// A side condition on the variables bound by a pattern.
+#[derive(Debug, Clone)]
+pub enum Guard {
+    Equal(AST, AST),
+    NotEqual(AST, AST),
+}
+
+/// A rewrite rule `pattern = expr if guards`, and the section of the rule
+/// file it was written in.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    /// Empty for rules before the first `section` header.
+    pub section: String,
+    pub pattern: AST,
+    pub expr: AST,
+    /// Every guard has to hold for the rule to apply.
+    pub guards: Vec<Guard>,
+}
+
+impl Rule {
+    pub fn new(pattern: AST, expr: AST) -> Rule {
+        return Rule { section: String::new(), pattern, expr, guards: vec![] };
+    }
+}
+
+impl Display for Guard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Guard::Equal(a, b) => write!(f, "{a} == {b}"),
+            Guard::NotEqual(a, b) => write!(f, "{a} != {b}"),
+        }
+    }
+}
+
+/// The rule as a line of a rule file.
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} = {}", self.pattern, self.expr)?;
+
+        for (i, guard) in self.guards.iter().enumerate() {
+            write!(f, "{} {guard}", if i == 0 { " if" } else { " and" })?;
+        }
+
+        return Ok(());
+    }
+}
+
+/// Loads only the rules of the given sections, in file order.
+pub(crate) fn load_sections(path: &str, sections: &[&str]) -> Result<Vec<Rule>, Vec<ParseError>> {
+    let rules = load(path)?;
+    return Ok(rules.into_iter().filter(|rule| sections.contains(&rule.section.as_str())).collect());
+}
+
+/// Loads a rule file of `pattern = expr` lines, each optionally followed
+/// by guards like `if a != b and C(a, b) == 0`. Anything after a `#` is a
+/// comment, `section name` starts a named group of rules, and
+/// `include "file"` loads another rule file, relative to this one, in place.
+/// Rules of an included file before its own first header stay in the
+/// section it was included from. Every bad line is reported, not just the
+/// first.
+pub fn load(path: &str) -> Result<Vec<Rule>, Vec<ParseError>> {
+    let Ok(src) = std::fs::read_to_string(path) else {
+        return Err(vec![ParseError::new(path, 1, "", 0, Expected::File)]);
+    };
+
+    let mut loader = Loader { rules: vec![], errors: vec![], stack: vec![] };
+    loader.load(Path::new(path), &src, "");
+
+    if !loader.errors.is_empty() {
+        return Err(loader.errors);
+    }
+
+    return Ok(loader.rules);
+}
+
+struct Loader {
+    rules: Vec<Rule>,
+    errors: Vec<ParseError>,
+    /// The files being loaded, to catch a file that includes itself.
+    stack: Vec<PathBuf>,
 }
 
-fn parse_value(src: Parser) -> Option<(Parser, AST)> {
-    let (src, a) = parse_paren(src)
-        .or_else(|| parse_c(src))
-        .or_else(|| parse_named(src))
-        .or_else(|| parse_number_value(src))
-        .or_else(|| parse_braket(src))
-        .or_else(|| parse_negative(src))?;
+impl Loader {
+    fn load(&mut self, path: &Path, src: &str, section: &str) {
+        let file = path.display().to_string();
+        let mut section = section.to_string();
 
+        self.stack.push(std::fs::canonicalize(path).unwrap_or(path.to_path_buf()));
+
+        for (i, text) in src.lines().enumerate() {
+            let line = text.split('#').next().unwrap_or("");
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let directive = Parser { src: line, index: 0 }.skip_whitespace();
+
+            if !line.contains('=') {
+                if let Ok((after, word)) = parse_word(directive) {
+                    match word.as_str() {
+                        "section" => {
+                            match parse_section(after) {
+                                Ok(name) => section = name,
+                                Err(err) => self.errors.push(ParseError::new(&file, i + 1, text, err.index, err.expected)),
+                            }
+                            continue;
+                        }
+                        "include" => {
+                            match parse_include(after) {
+                                Ok((index, name)) => self.include(path, &name, &section, (&file, i + 1, text, index)),
+                                Err(err) => self.errors.push(ParseError::new(&file, i + 1, text, err.index, err.expected)),
+                            }
+                            continue;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            match parse_rule(line) {
+                Ok((pattern, expr, guards)) => self.rules.push(Rule { section: section.clone(), pattern, expr, guards }),
+                Err(failures) => {
+                    for err in failures {
+                        self.errors.push(ParseError::new(&file, i + 1, text, err.index, err.expected));
+                    }
+                }
+            }
+        }
+
+        self.stack.pop();
+    }
+
+    /// Loads `name`, relative to the file at `from`, with `at` the line of
+    /// the directive to blame if that fails.
+    fn include(&mut self, from: &Path, name: &str, section: &str, at: (&str, usize, &str, usize)) {
+        let (file, line, text, index) = at;
+        let path = from.parent().unwrap_or(Path::new("")).join(name);
+
+        let Ok(src) = std::fs::read_to_string(&path) else {
+            self.errors.push(ParseError::new(file, line, text, index, Expected::File));
+            return;
+        };
+
+        if self.stack.contains(&std::fs::canonicalize(&path).unwrap_or(path.clone())) {
+            self.errors.push(ParseError::new(file, line, text, index, Expected::NotIncluded));
+            return;
+        }
+
+        self.load(&path, &src, section);
+    }
+}
+
+/// A `pattern = expr if guards` line, with every failure on it. Patterns
+/// never contain `=`, so the first one splits the rule.
+fn parse_rule(line: &str) -> Result<(AST, AST, Vec<Guard>), Vec<Failure>> {
+    let Some(eq) = line.find('=') else {
+        return Err(vec![Failure { index: line.trim_end().len(), expected: Expected::Char('=') }]);
+    };
+
+    let pattern = parse_all(&line[..eq]);
+    let expr = parse_guarded(Parser { src: line, index: eq + 1 }.skip_whitespace());
+
+    match (pattern, expr) {
+        (Ok(pattern), Ok((expr, guards))) => return Ok((pattern, expr, guards)),
+        (pattern, expr) => return Err([pattern.err(), expr.err()].into_iter().flatten().collect()),
+    }
+}
+
+/// An expression running to the end of the line, or up to an `if` and the
+/// guards after it.
+fn parse_guarded(src: Parser) -> Result<(AST, Vec<Guard>), Failure> {
+    let (src, expr) = parse_value(src)?;
     let src = src.skip_whitespace();
 
-    if let Some(src) = src.starts_with_char('+') {
-        let src = src.skip_whitespace();
+    if src.index == src.src.len() {
+        return Ok((expr, vec![]));
+    }
 
-        let (src, b) = parse_value(src)?;
+    let (mut src, word) = parse_word(src).map_err(|_| src.fail(Expected::End))?;
 
-        return Some((src, AST::Add(Box::new(a), Box::new(b))))
+    if word != "if" {
+        return Err(Failure { index: src.index - word.len(), expected: Expected::End });
     }
 
-    if let Some(src) = src.starts_with_char('-') {
-        let src = src.skip_whitespace();
+    let mut guards = vec![];
 
-        let (src, b) = parse_value(src)?;
+    loop {
+        let (after, guard) = parse_guard(src.skip_whitespace())?;
+        guards.push(guard);
 
-        return Some((src, AST::Add(Box::new(a), Box::new(AST::Negative(Box::new(b))))))
+        src = after.skip_whitespace();
+
+        if src.index == src.src.len() {
+            return Ok((expr, guards));
+        }
+
+        match parse_word(src) {
+            Ok((after, word)) if word == "and" => src = after,
+            _ => return Err(src.fail(Expected::End)),
+        }
     }
+}
 
-    if let Some(src) = src.starts_with_char('*') {
-        let src = src.skip_whitespace();
+/// `a == b` or `a != b`.
+fn parse_guard(src: Parser) -> Parsed<Guard> {
+    let (src, a) = parse_value(src)?;
 
-        let (src, b) = parse_value(src)?;
+    let (src, equal) = if let Some(after) = src.starts_with_char('=').and_then(|src| src.starts_with_char('=')) {
+        (after, true)
+    } else if let Some(after) = src.starts_with_char('!').and_then(|src| src.starts_with_char('=')) {
+        (after, false)
+    } else {
+        return Err(src.fail(Expected::Comparison));
+    };
+
+    let (src, b) = parse_value(src.skip_whitespace())?;
+
+    return Ok((src, if equal { Guard::Equal(a, b) } else { Guard::NotEqual(a, b) }));
+}
+
+/// The name after `section`, and nothing else.
+fn parse_section(src: Parser) -> Result<String, Failure> {
+    let src = src.skip_whitespace();
+    let (src, name) = parse_word(src)?;
+    let src = src.skip_whitespace();
 
-        return Some((src, AST::Mul(Box::new(a), Box::new(b))))
+    if src.index < src.src.len() {
+        return Err(src.fail(Expected::End));
     }
 
-    return Some((src, a));
+    return Ok(name);
 }
 
-pub fn parse(src: &str) -> AST {
-    if let Some((_, ast)) = parse_value(Parser { src, index: 0 }) {
-        return ast;
+/// The quoted file name after `include`, with the index it starts at.
+fn parse_include(src: Parser) -> Result<(usize, String), Failure> {
+    let src = src.skip_whitespace();
+    let start = src.index;
+    let src = src.expect_char('"')?;
+    let name = src.next_while(|chr| chr != '"');
+    let end = name.index;
+    let src = name.expect_char('"')?.skip_whitespace();
+
+    if src.index < src.src.len() {
+        return Err(src.fail(Expected::End));
     }
 
-    panic!("Failed to parse {src}!");
+    return Ok((start, src.src[start + 1..end].to_string()));
 }
 
-pub fn load(path:  &str) -> Vec<(AST, AST)> {
-    std::fs::read_to_string(path)
-        .expect(&format!("Could not find source file {path}!"))
-        .lines()
-        .map(|line| line.trim())
-        .filter(|line| line.len() != 0)
-        .map(|line| {
-            if let [pattern_src, expr_src] = line.split('=').collect::<Vec<&str>>()[..] {
-                let pattern = parse(pattern_src.trim());
-                let expr = parse(expr_src.trim());
-                return (pattern, expr);
-            } else {
-                panic!("Invalid expression {line}, could not find equal statment!");
-            }
-        })
-        .collect::<Vec<(AST, AST)>>()
-}
\ No newline at end of file
+///////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The tree as an s-expression, with `-` for negation.
+    fn show(ast: &AST) -> String {
+        match ast {
+            AST::Named(name) => name.clone(),
+            AST::Value(value) => format!("{value}"),
+            AST::Kind(name, value) => format!("{name}({value})"),
+            AST::Negative(a) => format!("(- {})", show(a)),
+            AST::Braket(a, b) => format!("[{} {}]", show(a), show(b)),
+            AST::Add(a, b) => format!("(+ {} {})", show(a), show(b)),
+            AST::Mul(a, b) => format!("(* {} {})", show(a), show(b)),
+            AST::C(a, b) => format!("(C {} {})", show(a), show(b)),
+        }
+    }
+
+    fn tree(src: &str) -> String {
+        return show(&parse(src).unwrap_or_else(|err| panic!("{err}")));
+    }
+
+    #[test]
+    fn subtraction_is_left_associative() {
+        assert_eq!(tree("a - b + c"), "(+ (+ a (- b)) c)");
+        assert_eq!(tree("a - b - c"), "(+ (+ a (- b)) (- c))");
+        assert_eq!(tree("a + b - c"), "(+ (+ a b) (- c))");
+    }
+
+    #[test]
+    fn product_binds_tighter_than_sum() {
+        assert_eq!(tree("2 * x + y"), "(+ (* 2 x) y)");
+        assert_eq!(tree("x + 2 * y"), "(+ x (* 2 y))");
+        assert_eq!(tree("x - 2 * y"), "(+ x (- (* 2 y)))");
+        assert_eq!(tree("a * b * c"), "(* (* a b) c)");
+        assert_eq!(tree("(a + b) * c"), "(* (+ a b) c)");
+    }
+
+    #[test]
+    fn negation_takes_the_product_after_it() {
+        assert_eq!(tree("-a + b"), "(+ (- a) b)");
+        assert_eq!(tree("-a * b"), "(- (* a b))");
+        assert_eq!(tree("--a"), "(- (- a))");
+        assert_eq!(tree("-(a + b)"), "(- (+ a b))");
+    }
+
+    #[test]
+    fn mixed_rule_expressions() {
+        assert_eq!(tree("[nx_h, E(n)] - C(b, n) * [nx, E(n)]"), "(+ [nx_h E(n)] (- (* (C b n) [nx E(n)])))");
+        assert_eq!(tree("[[a, E(b)], F(c)]"), "[[a E(b)] F(c)]");
+        assert_eq!(tree("[x + y, z] - 3 * [x, z] + [y, z]"), "(+ (+ [(+ x y) z] (- (* 3 [x z]))) [y z])");
+        assert_eq!(tree("-C(a, b) * F(b)"), "(- (* (C a b) F(b)))");
+        assert_eq!(tree("E(1)"), "E(1)");
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_character() {
+        let err = parse("[a, b").unwrap_err();
+        assert_eq!((err.line, err.column, err.expected), (1, 6, Expected::Char(']')));
+
+        let err = parse("a b").unwrap_err();
+        assert_eq!((err.column, err.expected), (3, Expected::End));
+
+        let err = parse("a + * b").unwrap_err();
+        assert_eq!((err.column, err.expected), (5, Expected::Value));
+
+        assert_eq!(err.to_string(), "<input>:1:5: expected a value\n    a + * b\n        ^");
+    }
+
+    #[test]
+    fn rule_file_loads() {
+        assert!(load("./src/map").is_ok());
+    }
+
+    /// A directory of rule files, removed again when the test is done with
+    /// it, failed or not.
+    struct RuleFiles(PathBuf);
+
+    impl std::ops::Deref for RuleFiles {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            return &self.0;
+        }
+    }
+
+    impl Drop for RuleFiles {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Writes rule files into a fresh directory.
+    fn rule_files(name: &str, files: &[(&str, &str)]) -> RuleFiles {
+        let dir = RuleFiles(std::env::temp_dir().join(format!("proof-parser-{name}-{}", std::process::id())));
+        std::fs::create_dir_all(&*dir).expect("Could not create test directory!");
+
+        for (file, src) in files {
+            std::fs::write(dir.join(file), src).expect("Could not write test rule file!");
+        }
+
+        return dir;
+    }
+
+    fn sections(rules: &[Rule]) -> Vec<(String, String)> {
+        return rules.iter().map(|rule| (rule.section.clone(), show(&rule.pattern))).collect();
+    }
+
+    #[test]
+    fn comments_and_sections() {
+        let dir = rule_files("sections", &[("main.rules", "# header\na + 0 = a # trailing\n\nsection one\n[a, a] = 0\nsection two\n-0 = 0\n")]);
+        let path = dir.join("main.rules").display().to_string();
+
+        let rules = load(&path).unwrap();
+        assert_eq!(sections(&rules), [
+            ("".to_string(), "(+ a 0)".to_string()),
+            ("one".to_string(), "[a a]".to_string()),
+            ("two".to_string(), "(- 0)".to_string()),
+        ]);
+
+        assert_eq!(load_sections(&path, &["two"]).unwrap().len(), 1);
+        assert_eq!(load_sections(&path, &["one", "two"]).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn includes_are_relative_and_keep_the_section() {
+        let dir = rule_files("include", &[
+            ("main.rules", "section outer\ninclude \"inner.rules\"\n[a, a] = 0\n"),
+            ("inner.rules", "a + 0 = a\nsection inner\n-0 = 0\n"),
+        ]);
+
+        let rules = load(&dir.join("main.rules").display().to_string()).unwrap();
+        assert_eq!(sections(&rules), [
+            ("outer".to_string(), "(+ a 0)".to_string()),
+            ("inner".to_string(), "(- 0)".to_string()),
+            ("outer".to_string(), "[a a]".to_string()),
+        ]);
+    }
+
+    fn guards(rule: &Rule) -> Vec<String> {
+        return rule.guards
+            .iter()
+            .map(|guard| match guard {
+                Guard::Equal(a, b) => format!("{} == {}", show(a), show(b)),
+                Guard::NotEqual(a, b) => format!("{} != {}", show(a), show(b)),
+            })
+            .collect();
+    }
+
+    #[test]
+    fn guards_follow_the_expression() {
+        let dir = rule_files("guards", &[
+            ("main.rules", "[E(a), F(b)] = 0 if a != b\n[E(a), E(b)] = 0 if C(a, b) == 0 and a != b\n[H(a), E(b)] = C(a, b) * E(b)\n"),
+        ]);
+
+        let rules = load(&dir.join("main.rules").display().to_string()).unwrap();
+
+        assert_eq!(guards(&rules[0]), ["a != b"]);
+        assert_eq!(guards(&rules[1]), ["(C a b) == 0", "a != b"]);
+        assert_eq!(show(&rules[1].expr), "0");
+        assert!(rules[2].guards.is_empty());
+    }
+
+    #[test]
+    fn c_is_found_anywhere_in_a_tree() {
+        assert!(parse("[H(a), E(b)] - C(a, b) * E(b)").unwrap().has_c());
+        assert!(parse("-[E(a), C(a, b)]").unwrap().has_c());
+        assert!(!parse("[H(a), E(b)] - 2 * E(b)").unwrap().has_c());
+    }
+
+    #[test]
+    fn every_bad_line_is_reported() {
+        let dir = rule_files("errors", &[
+            ("main.rules", "[a, b = c\nno equals here\ninclude \"missing.rules\"\ninclude \"main.rules\"\na = b if a < b\na = b unless a\n"),
+        ]);
+
+        let errors = load(&dir.join("main.rules").display().to_string()).unwrap_err();
+        let errors = errors.iter().map(|err| (err.line, err.column, err.expected)).collect::<Vec<_>>();
+
+        assert_eq!(errors, [
+            (1, 7, Expected::Char(']')),
+            (2, 15, Expected::Char('=')),
+            (3, 9, Expected::File),
+            (4, 9, Expected::NotIncluded),
+            (5, 12, Expected::Comparison),
+            (6, 7, Expected::End),
+        ]);
+    }
+
+    #[test]
+    fn errors_name_the_file_and_line_they_come_from() {
+        let dir = rule_files("spans", &[
+            ("main.rules", "a + 0 = a\ninclude \"other.rules\"\n"),
+            ("other.rules", "# swapped\n[a, b] = -[b, a] +\n"),
+        ]);
+
+        let errors = load(&dir.join("main.rules").display().to_string()).unwrap_err();
+        let other = dir.join("other.rules").display().to_string();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].file.as_str(), errors[0].line, errors[0].column), (other.as_str(), 2, 19));
+        assert_eq!(errors[0].to_string(), format!("{other}:2:19: expected a value\n    [a, b] = -[b, a] +\n                      ^"));
+    }
+
+    #[test]
+    fn rules_print_back_as_they_parse() {
+        for rule in load("./src/map").unwrap() {
+            let line = rule.to_string();
+            let (pattern, expr, guards) = parse_rule(&line).unwrap_or_else(|_| panic!("Could not parse back {line}"));
+
+            assert_eq!(show(&pattern), show(&rule.pattern), "{line}");
+            assert_eq!(show(&expr), show(&rule.expr), "{line}");
+            assert_eq!(guards.len(), rule.guards.len(), "{line}");
+        }
+
+        for src in ["a - (b - c)", "(a - b) - c", "-(a + b) * c", "(-a) * b", "a * (b * c)", "-(-a)", "[-a, b] - 2 * [a, b]"] {
+            assert_eq!(tree(&parse(src).unwrap().to_string()), tree(src), "{src}");
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use std::io::{BufRead, Write};
+use std::sync::Arc;
+
+use crate::cartan::CartanMatrix;
+use crate::derivation::{equals_sign, is_identifier};
+use crate::error::ProofError;
+use crate::parser::AST;
+use crate::{RuleSet, Scope};
+
+const HELP: &str = "\
+An expression such as [[E(1), E(2)], F(1)] is simplified and printed.
+  let x = ...       bind x for later lines, an index or a simplified term
+  :rules <file>     simplify with another rule file
+  :cartan [matrix]  print the matrix, or switch to a Dynkin type or file
+  :trace            turn printing every rewrite on or off
+  :history          list the lines entered so far
+  !!, !n            run the last line again, or line n
+  :quit             leave, as does end of input";
+
+/// What a session has set up so far.
+struct Session {
+    scope: Scope,
+    rules_path: String,
+    /// The sections of each rule file to keep, all of them for `None`.
+    sections: Option<Vec<String>>,
+    rules: RuleSet,
+    trace: bool,
+    history: Vec<String>,
+}
+
+impl Session {
+    fn run(&mut self, line: &str) -> Result<(), String> {
+        if line == ":help" {
+            println!("{HELP}");
+            return Ok(());
+        }
+
+        if line == ":trace" {
+            self.trace = !self.trace;
+            println!("Tracing is {}", if self.trace { "on" } else { "off" });
+            return Ok(());
+        }
+
+        if line == ":history" {
+            for (i, line) in self.history.iter().enumerate() {
+                println!("{:>4}  {line}", i + 1);
+            }
+
+            return Ok(());
+        }
+
+        if let Some(path) = line.strip_prefix(":rules") {
+            let path = path.trim();
+
+            if path.is_empty() {
+                println!("{} rules from {}", self.rules.len(), self.rules_path);
+                return Ok(());
+            }
+
+            self.rules = load(path, &self.sections, &self.scope.cartan).map_err(|err| err.to_string())?;
+            self.rules_path = path.to_string();
+            println!("{} rules from {path}", self.rules.len());
+            return Ok(());
+        }
+
+        if let Some(arg) = line.strip_prefix(":cartan") {
+            let arg = arg.trim();
+
+            if !arg.is_empty() {
+                let cartan = CartanMatrix::from_arg(arg).map_err(|err| err.to_string())?;
+
+                if !cartan.validate().is_empty() {
+                    print!("{}", cartan.report());
+                    return Err(format!("{arg} is not a Cartan matrix"));
+                }
+
+                // The Serre relations at the front of the rules come from the matrix.
+                self.rules = load(&self.rules_path, &self.sections, &cartan).map_err(|err| err.to_string())?;
+                self.scope.cartan = Arc::new(cartan);
+            }
+
+            print!("{}", self.scope.cartan.report());
+            return Ok(());
+        }
+
+        if line.starts_with(':') {
+            return Err(format!("Unknown command {line}, :help lists them"));
+        }
+
+        let assigned = equals_sign(line).map(|i| (line[..i].trim(), line[i + 1..].trim()));
+
+        if let Some((lhs, rhs)) = assigned {
+            let name = lhs.strip_prefix("let ").unwrap_or(lhs).trim();
+
+            if !is_identifier(name) {
+                return Err(format!("Cannot assign to {lhs}"));
+            }
+
+            let value = crate::script::value(rhs, &self.rules, &self.scope)?;
+            println!("{name} = {}", AST::from_value(&value));
+            self.scope.set(name, value);
+            return Ok(());
+        }
+
+        let start = crate::script::build(line, &self.scope)?;
+
+        if self.trace {
+            let trace = crate::simplify_traced(start, &self.rules, &self.scope).map_err(|err| err.to_string())?;
+            print!("{trace}");
+            return Ok(());
+        }
+
+        let value = crate::script::value(line, &self.rules, &self.scope)?;
+        println!("{}", AST::from_value(&value));
+        return Ok(());
+    }
+
+    /// The line `!!` or `!n` stands for, or `line` itself.
+    fn recall(&self, line: &str) -> Result<String, String> {
+        let Some(n) = line.strip_prefix('!') else {
+            return Ok(line.to_string());
+        };
+
+        let entry = match n {
+            "!" => self.history.last(),
+            n => n.parse::<usize>().ok().and_then(|n| self.history.get(n.wrapping_sub(1))),
+        };
+
+        return entry.cloned().ok_or(format!("No line {line} in the history"));
+    }
+}
+
+fn load(path: &str, sections: &Option<Vec<String>>, cartan: &CartanMatrix) -> Result<RuleSet, ProofError> {
+    match sections {
+        Some(sections) => return RuleSet::load_sections(path, &sections.iter().map(String::as_str).collect::<Vec<&str>>(), cartan),
+        None => return RuleSet::load(path, cartan),
+    }
+}
+
+/// Reads expressions and commands from standard input until it ends,
+/// simplifying each expression with the rules at `rules`, only those of
+/// `sections` if given.
+pub fn run(rules: &str, sections: Option<Vec<String>>, scope: Scope) -> Result<(), ProofError> {
+    let rules_path = rules.to_string();
+    let rules = load(rules, &sections, &scope.cartan)?;
+    let mut session = Session { scope, rules_path, sections, rules, trace: false, history: vec![] };
+
+    println!("{} rules from {}, :help for help", session.rules.len(), session.rules_path);
+
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    loop {
+        print!("> ");
+        std::io::stdout().flush().expect("Could not write to standard output!");
+
+        let Some(Ok(line)) = lines.next() else {
+            println!();
+            return Ok(());
+        };
+
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if line == ":quit" || line == ":q" {
+            return Ok(());
+        }
+
+        let line = match session.recall(line) {
+            Ok(recalled) if recalled != line => {
+                println!("{recalled}");
+                recalled
+            }
+            Ok(line) => line,
+            Err(reason) => {
+                println!("{reason}");
+                continue;
+            }
+        };
+
+        session.history.push(line.clone());
+
+        if let Err(reason) = session.run(&line) {
+            println!("{reason}");
+        }
+    }
+}
+
+///////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> Session {
+        let cartan = Arc::new(CartanMatrix::dynkin('A', 2).unwrap());
+        let rules = load("./src/map", &None, &cartan).unwrap_or_else(|err| panic!("{err}"));
+
+        return Session { scope: Scope::new(&cartan), rules_path: "./src/map".to_string(), sections: None, rules, trace: false, history: vec![] };
+    }
+
+    #[test]
+    fn lets_bind_simplified_values() {
+        let mut session = session();
+
+        session.run("let x = [E(1), E(2)] + [E(2), E(1)]").unwrap();
+        assert_eq!(AST::from_value(&session.scope.get("x").unwrap()).to_string(), "0");
+
+        session.run("y = [E(1), E(2)]").unwrap();
+        assert!(session.scope.get("y").is_some());
+
+        assert_eq!(session.run("let [x, y] = 0"), Err("Cannot assign to let [x, y]".to_string()));
+        assert_eq!(session.run(":nope"), Err("Unknown command :nope, :help lists them".to_string()));
+    }
+
+    #[test]
+    fn history_is_recalled_by_number() {
+        let mut session = session();
+        session.history = vec!["E(1)".to_string(), "F(2)".to_string()];
+
+        assert_eq!(session.recall("!!"), Ok("F(2)".to_string()));
+        assert_eq!(session.recall("!1"), Ok("E(1)".to_string()));
+        assert_eq!(session.recall("E(2)"), Ok("E(2)".to_string()));
+        assert!(session.recall("!0").is_err() && session.recall("!3").is_err());
+    }
+}
@@ -0,0 +1,396 @@
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+
+use crate::integer::Integer;
+
+/// The coefficient ring of a bracket polynomial.
+///
+/// The ring is passed around as a value rather than only as a type, so that
+/// `Modular` can carry the prime it was chosen with.
+pub trait Ring: Clone + Debug + Send + Sync {
+    type Elem: Clone + PartialEq + Eq + Display + Debug + Send + Sync;
+
+    fn lift(&self, n: &Integer) -> Self::Elem;
+
+    fn add(&self, a: &Self::Elem, b: &Self::Elem) -> Self::Elem;
+
+    fn mul(&self, a: &Self::Elem, b: &Self::Elem) -> Self::Elem;
+
+    fn neg(&self, a: &Self::Elem) -> Self::Elem;
+
+    fn is_zero(&self, a: &Self::Elem) -> bool;
+
+    /// The multiplicative inverse, if `a` is a unit.
+    fn inverse(&self, a: &Self::Elem) -> Option<Self::Elem>;
+
+    /// The element as an integer, if it is one. Elements of `Z/p` are
+    /// written with the representative closest to zero.
+    fn to_integer(&self, a: &Self::Elem) -> Option<Integer>;
+
+    /// Reads an element back from its `Display` form.
+    fn parse(&self, src: &str) -> Option<Self::Elem>;
+
+    fn lift_i32(&self, n: i32) -> Self::Elem {
+        return self.lift(&Integer::from(n));
+    }
+
+    fn zero(&self) -> Self::Elem {
+        return self.lift_i32(0);
+    }
+
+    /// Divides out a common factor of `elems`, to keep eliminations that
+    /// scale rather than divide from growing. Over a field there is nothing
+    /// worth dividing out.
+    fn primitive<'a>(&self, _elems: impl Iterator<Item = &'a mut Self::Elem>) where Self::Elem: 'a {}
+}
+
+///////
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Integers;
+
+impl Ring for Integers {
+    type Elem = Integer;
+
+    fn lift(&self, n: &Integer) -> Integer {
+        return n.clone();
+    }
+
+    fn add(&self, a: &Integer, b: &Integer) -> Integer {
+        return a.clone() + b.clone();
+    }
+
+    fn mul(&self, a: &Integer, b: &Integer) -> Integer {
+        return a.clone() * b.clone();
+    }
+
+    fn neg(&self, a: &Integer) -> Integer {
+        return -a.clone();
+    }
+
+    fn is_zero(&self, a: &Integer) -> bool {
+        return a.is_zero();
+    }
+
+    fn inverse(&self, a: &Integer) -> Option<Integer> {
+        return if a.abs().is_one() { Some(a.clone()) } else { None };
+    }
+
+    fn to_integer(&self, a: &Integer) -> Option<Integer> {
+        return Some(a.clone());
+    }
+
+    fn parse(&self, src: &str) -> Option<Integer> {
+        return src.parse().ok();
+    }
+
+    fn primitive<'a>(&self, elems: impl Iterator<Item = &'a mut Integer>) {
+        let mut elems = elems.collect::<Vec<&mut Integer>>();
+        let content = elems.iter().fold(Integer::zero(), |g, n| g.gcd(n));
+
+        if !content.is_zero() && !content.is_one() {
+            elems.iter_mut().for_each(|n| **n = n.div_rem(&content).0);
+        }
+    }
+}
+
+///////
+
+/// A fraction in lowest terms with a positive denominator.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: Integer,
+    den: Integer,
+}
+
+impl Rational {
+    pub fn new(num: Integer, den: Integer) -> Rational {
+        if den.is_zero() {
+            panic!("Rational {num}/{den} has a zero denominator!");
+        }
+
+        let mut g = num.gcd(&den);
+
+        if den.is_negative() {
+            g = -g;
+        }
+
+        return Rational { num: num.div_rem(&g).0, den: den.div_rem(&g).0 };
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.den.is_one() {
+            return write!(f, "{}", self.num);
+        }
+
+        return write!(f, "{}/{}", self.num, self.den);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rationals;
+
+impl Ring for Rationals {
+    type Elem = Rational;
+
+    fn lift(&self, n: &Integer) -> Rational {
+        return Rational { num: n.clone(), den: Integer::one() };
+    }
+
+    fn add(&self, a: &Rational, b: &Rational) -> Rational {
+        if a.den.is_one() && b.den.is_one() {
+            return self.lift(&(a.num.clone() + b.num.clone()));
+        }
+
+        return Rational::new(
+            a.num.clone() * b.den.clone() + b.num.clone() * a.den.clone(),
+            a.den.clone() * b.den.clone(),
+        );
+    }
+
+    fn mul(&self, a: &Rational, b: &Rational) -> Rational {
+        if a.den.is_one() && b.den.is_one() {
+            return self.lift(&(a.num.clone() * b.num.clone()));
+        }
+
+        return Rational::new(a.num.clone() * b.num.clone(), a.den.clone() * b.den.clone());
+    }
+
+    fn neg(&self, a: &Rational) -> Rational {
+        return Rational { num: -a.num.clone(), den: a.den.clone() };
+    }
+
+    fn is_zero(&self, a: &Rational) -> bool {
+        return a.num.is_zero();
+    }
+
+    fn inverse(&self, a: &Rational) -> Option<Rational> {
+        if a.num.is_zero() {
+            return None;
+        }
+
+        return Some(Rational::new(a.den.clone(), a.num.clone()));
+    }
+
+    fn to_integer(&self, a: &Rational) -> Option<Integer> {
+        return if a.den.is_one() { Some(a.num.clone()) } else { None };
+    }
+
+    fn parse(&self, src: &str) -> Option<Rational> {
+        let Some((num, den)) = src.split_once('/') else {
+            return Some(self.lift(&src.parse().ok()?));
+        };
+
+        let den = den.parse::<Integer>().ok()?;
+
+        if den.is_zero() {
+            return None;
+        }
+
+        return Some(Rational::new(num.parse().ok()?, den));
+    }
+}
+
+///////
+
+/// The integers modulo a prime `p < 2^63`.
+///
+/// Reducing mod a large prime keeps every coefficient one machine word, at
+/// the price of a zero test that is only probabilistic: a non zero integer
+/// result vanishes mod `p` exactly when `p` divides all of its coefficients.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Modular {
+    p: u64,
+}
+
+impl Modular {
+    /// The largest prime below `2^61`, used when no prime is given.
+    pub const DEFAULT_PRIME: u64 = (1 << 61) - 1;
+
+    pub fn new(p: u64) -> Option<Modular> {
+        if p >= 1 << 63 || !is_prime(p) {
+            return None;
+        }
+
+        return Some(Modular { p });
+    }
+
+    pub fn prime(&self) -> u64 {
+        return self.p;
+    }
+
+    fn pow(&self, mut base: u64, mut exp: u64) -> u64 {
+        let mut out = 1;
+
+        while exp > 0 {
+            if exp & 1 == 1 {
+                out = mul_mod(out, base, self.p);
+            }
+
+            base = mul_mod(base, base, self.p);
+            exp >>= 1;
+        }
+
+        return out;
+    }
+}
+
+fn mul_mod(a: u64, b: u64, p: u64) -> u64 {
+    return (a as u128 * b as u128 % p as u128) as u64;
+}
+
+/// Deterministic Miller-Rabin, exact for every `u64`.
+fn is_prime(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+
+    for p in [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37] {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    let ring = Modular { p: n };
+
+    return [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37].iter().all(|a| {
+        let mut x = ring.pow(*a, d);
+
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+
+            if x == n - 1 {
+                return true;
+            }
+        }
+
+        return false;
+    });
+}
+
+impl Ring for Modular {
+    type Elem = u64;
+
+    fn lift(&self, n: &Integer) -> u64 {
+        let r = n.div_rem(&Integer::from(self.p as i64)).1;
+        let r = r.to_i64().expect("Remainder mod p fits in a machine word!");
+
+        return if r < 0 { (r + self.p as i64) as u64 } else { r as u64 };
+    }
+
+    fn add(&self, a: &u64, b: &u64) -> u64 {
+        return ((*a as u128 + *b as u128) % self.p as u128) as u64;
+    }
+
+    fn mul(&self, a: &u64, b: &u64) -> u64 {
+        return mul_mod(*a, *b, self.p);
+    }
+
+    fn neg(&self, a: &u64) -> u64 {
+        return if *a == 0 { 0 } else { self.p - a };
+    }
+
+    fn is_zero(&self, a: &u64) -> bool {
+        return *a == 0;
+    }
+
+    fn inverse(&self, a: &u64) -> Option<u64> {
+        if *a == 0 {
+            return None;
+        }
+
+        return Some(self.pow(*a, self.p - 2));
+    }
+
+    fn to_integer(&self, a: &u64) -> Option<Integer> {
+        if *a > self.p / 2 {
+            return Some(-Integer::from((self.p - a) as i64));
+        }
+
+        return Some(Integer::from(*a as i64));
+    }
+
+    fn parse(&self, src: &str) -> Option<u64> {
+        return src.parse::<u64>().ok().filter(|a| *a < self.p);
+    }
+}
+
+///////
+
+/// A coefficient ring picked at run time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Coefficients {
+    Integers,
+    Rationals,
+    Modular(Modular),
+}
+
+impl FromStr for Coefficients {
+    type Err = ();
+
+    /// Parses `Z`, `Q`, `Z/p` or `mod` for the default prime.
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        match src.trim() {
+            "Z" | "int" => return Ok(Coefficients::Integers),
+            "Q" | "rational" => return Ok(Coefficients::Rationals),
+            "mod" => return Ok(Coefficients::Modular(Modular { p: Modular::DEFAULT_PRIME })),
+            src => {
+                let p = src.strip_prefix("Z/").ok_or(())?.parse::<u64>().map_err(|_| ())?;
+                return Ok(Coefficients::Modular(Modular::new(p).ok_or(())?));
+            }
+        }
+    }
+}
+
+impl Display for Coefficients {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Coefficients::Integers => write!(f, "Z"),
+            Coefficients::Rationals => write!(f, "Q"),
+            Coefficients::Modular(ring) => write!(f, "Z/{}", ring.p),
+        }
+    }
+}
+
+///////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negative_integers_lift_to_their_residue() {
+        let ring = Modular::new(7).unwrap();
+
+        assert_eq!(ring.lift_i32(-1), 6);
+        assert_eq!(ring.lift_i32(-15), 6);
+        assert_eq!(ring.lift_i32(-14), 0);
+        assert_eq!(ring.lift(&-Integer::from(i64::MIN)), 1);
+        assert_eq!(ring.to_integer(&ring.lift_i32(-3)), Some(Integer::from(-3)));
+    }
+
+    #[test]
+    fn lifts_agree_with_ring_arithmetic() {
+        let ring = Modular::new(Modular::DEFAULT_PRIME).unwrap();
+        let minus_two = ring.lift_i32(-2);
+
+        assert!(ring.is_zero(&ring.add(&minus_two, &ring.lift_i32(2))));
+        assert_eq!(ring.mul(&minus_two, &ring.inverse(&minus_two).unwrap()), 1);
+        assert_eq!(ring.neg(&minus_two), 2);
+    }
+
+    #[test]
+    fn only_primes_make_a_field() {
+        assert!(Modular::new(4).is_none());
+        assert!(Modular::new(1).is_none());
+        assert_eq!("Z/7".parse::<Coefficients>(), Ok(Coefficients::Modular(Modular::new(7).unwrap())));
+    }
+}
@@ -0,0 +1,464 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::cartan::CartanMatrix;
+use crate::error::ProofError;
+use crate::certificate::Substitution;
+use crate::derivation::{condition, equals_sign, expand, int, is_identifier, offset, Expr};
+use crate::integer::Integer;
+use crate::parser::{self, Rule, AST};
+use crate::value::*;
+use crate::{Budget, RuleSet, Scope, Verdict, MAX_STEPS};
+
+enum Statement {
+    /// `let x = ...`, or just `x = ...`: an index where the right-hand side
+    /// is index arithmetic, a term otherwise.
+    Let { line: usize, name: String, expr: Expr },
+    /// One case of a function of an index, `N(0) = E(1)` or
+    /// `N(n) = [N(n - 1), E(n % 3 + 1)]`.
+    Clause { name: String, param: String, body: String },
+    /// `for n in a..b { ... }`, with `n` from `a` up to `b - 1`.
+    For { line: usize, var: String, from: String, to: String, body: Vec<Statement> },
+    /// `if n == b { ... } else { ... }`, the `else` optional.
+    If { line: usize, condition: String, then: Vec<Statement>, otherwise: Vec<Statement> },
+    /// `stop when x == 0`: ends the script if `x` simplifies to zero.
+    Stop { line: usize, expr: String },
+    /// `print x`: writes out what `x` simplifies to.
+    Print { line: usize, expr: String },
+}
+
+/// What went wrong, on which line.
+type Failure = (usize, String);
+
+fn is_function(name: &str) -> bool {
+    return is_identifier(name) && name.starts_with(|chr: char| chr.is_ascii_uppercase()) && !["E", "F", "H", "C"].contains(&name);
+}
+
+///////
+
+struct Lines {
+    lines: Vec<(usize, String)>,
+    i: usize,
+}
+
+impl Lines {
+    fn peek(&self) -> Option<&(usize, String)> {
+        return self.lines.get(self.i);
+    }
+
+    fn expect(&mut self, text: &str) -> Result<(), Failure> {
+        match self.peek() {
+            Some((_, line)) if line == text => {
+                self.i += 1;
+                return Ok(());
+            }
+            Some((number, line)) => return Err((*number, format!("expected `{text}`, got `{line}`"))),
+            None => return Err((self.lines.last().map(|line| line.0).unwrap_or(0), format!("expected `{text}`"))),
+        }
+    }
+
+    /// An expression starting on line `number`, split into cases either on
+    /// one line, `if c { x } else { y }`, or over several the way `docs`
+    /// writes them.
+    fn expr(&mut self, number: usize, text: &str) -> Result<Expr, Failure> {
+        let Some(rest) = text.strip_prefix("if ") else {
+            return Ok(Expr::Plain(text.to_string()));
+        };
+
+        if let Some(condition) = rest.strip_suffix('{') {
+            let then = self.branch()?;
+            self.expect("} else {")?;
+            let otherwise = self.branch()?;
+            self.expect("}")?;
+
+            return Ok(Expr::If(condition.trim().to_string(), Box::new(then), Box::new(otherwise)));
+        }
+
+        let cases = || -> Option<(&str, &str, &str)> {
+            let (condition, rest) = rest.split_once('{')?;
+            let (then, rest) = rest.split_once('}')?;
+            let otherwise = rest.trim().strip_prefix("else")?.trim().strip_prefix('{')?.strip_suffix('}')?;
+            return Some((condition.trim(), then.trim(), otherwise.trim()));
+        };
+
+        let (condition, then, otherwise) = cases().ok_or((number, "expected `if c { x } else { y }`".to_string()))?;
+        let (then, otherwise) = (self.expr(number, then)?, self.expr(number, otherwise)?);
+
+        return Ok(Expr::If(condition.to_string(), Box::new(then), Box::new(otherwise)));
+    }
+
+    fn branch(&mut self) -> Result<Expr, Failure> {
+        let (number, text) = self.peek().cloned().ok_or((0, "expected an expression".to_string()))?;
+
+        if text.is_empty() || text.starts_with('}') {
+            return Err((number, "expected an expression".to_string()));
+        }
+
+        self.i += 1;
+        return self.expr(number, &text);
+    }
+
+    /// Statements up to the end of the file, or up to a `}` if `nested`.
+    fn statements(&mut self, nested: bool) -> Result<Vec<Statement>, Failure> {
+        let mut statements = vec![];
+
+        while let Some((number, text)) = self.peek().cloned() {
+            if text.is_empty() || text.starts_with('#') {
+                self.i += 1;
+                continue;
+            }
+
+            if text.starts_with('}') && nested {
+                break;
+            }
+
+            self.i += 1;
+
+            if let Some(rest) = text.strip_prefix("for ") {
+                let range = || -> Option<(String, String, String)> {
+                    let (var, range) = rest.strip_suffix('{')?.split_once(" in ")?;
+                    let (from, to) = range.trim().split_once("..")?;
+                    return Some((var.trim().to_string(), from.trim().to_string(), to.trim().to_string()));
+                };
+
+                let (var, from, to) = range().ok_or((number, "expected `for n in a..b {`".to_string()))?;
+                let body = self.statements(true)?;
+                self.expect("}")?;
+
+                statements.push(Statement::For { line: number, var, from, to, body });
+                continue;
+            }
+
+            if let Some(rest) = text.strip_prefix("if ") {
+                let condition = rest.strip_suffix('{').ok_or((number, "expected `{` after the condition".to_string()))?;
+                let then = self.statements(true)?;
+
+                let otherwise = match self.peek() {
+                    Some((_, line)) if line == "} else {" => {
+                        self.i += 1;
+                        self.statements(true)?
+                    }
+                    _ => vec![],
+                };
+
+                self.expect("}")?;
+
+                statements.push(Statement::If { line: number, condition: condition.trim().to_string(), then, otherwise });
+                continue;
+            }
+
+            if let Some(rest) = text.strip_prefix("stop when ") {
+                let expr = rest.strip_suffix("== 0").ok_or((number, "expected `stop when x == 0`".to_string()))?;
+                statements.push(Statement::Stop { line: number, expr: expr.trim().to_string() });
+                continue;
+            }
+
+            if let Some(rest) = text.strip_prefix("print ") {
+                statements.push(Statement::Print { line: number, expr: rest.trim().to_string() });
+                continue;
+            }
+
+            let Some(i) = equals_sign(&text) else {
+                return Err((number, format!("expected a statement, got `{text}`")));
+            };
+
+            let (lhs, rhs) = (text[..i].trim(), text[i + 1..].trim());
+            let clause = lhs.strip_suffix(')').and_then(|lhs| lhs.split_once('('));
+
+            if let Some((name, param)) = clause.filter(|(name, param)| is_function(name) && (is_identifier(param) || param.parse::<i64>().is_ok())) {
+                statements.push(Statement::Clause { name: name.to_string(), param: param.to_string(), body: rhs.to_string() });
+                continue;
+            }
+
+            let name = lhs.strip_prefix("let ").unwrap_or(lhs).trim();
+
+            if !is_identifier(name) || !name.starts_with(|chr: char| chr.is_ascii_lowercase()) {
+                return Err((number, format!("cannot assign to `{lhs}`")));
+            }
+
+            let expr = self.expr(number, rhs)?;
+            statements.push(Statement::Let { line: number, name: name.to_string(), expr });
+        }
+
+        return Ok(statements);
+    }
+}
+
+///////
+
+/// What a script ran with.
+struct Context<'a> {
+    rules: &'a Vec<Rule>,
+    clauses: HashMap<String, Vec<(String, String)>>,
+    offset: i64,
+}
+
+/// The indices bound in `scope`.
+fn ints(scope: &Scope) -> HashMap<String, i64> {
+    return scope
+        .vars
+        .iter()
+        .filter_map(|(name, value)| Some((name.clone(), value.into_number()?.to_i64()?)))
+        .collect();
+}
+
+fn set_int(scope: &mut Scope, name: &str, n: i64) {
+    scope.set(name, Value::Number(Integer::from(n)));
+}
+
+impl Context<'_> {
+    /// `src` built in `scope` with its index arithmetic worked out and its
+    /// functions called, not yet simplified.
+    fn build(&self, src: &str, scope: &Scope) -> Result<Value, String> {
+        let ints = ints(scope);
+        let mut bound = Substitution::new();
+
+        let functions = |name: &str| self.clauses.contains_key(name);
+        let call = |name: &str, arg: i64| self.call(name, arg, scope);
+        let src = expand(src, &ints, self.offset, &functions, &call, &mut bound)?;
+
+        let ast = parser::parse(&src).map_err(|err| err.to_string())?;
+
+        let mut scope = scope.clone();
+
+        for (name, value) in bound {
+            scope.set(&name, value);
+        }
+
+        return crate::ast_build(&ast, &scope).map_err(|err| err.to_string());
+    }
+
+    /// `src` built in `scope` and simplified, the way `make` does it.
+    fn term(&self, src: &str, scope: &Scope) -> Result<Value, String> {
+        return crate::reduce(self.build(src, scope)?, self.rules, &scope.cartan, scope.coefficients, &mut Budget::new(MAX_STEPS))
+            .map_err(|stop| stop.error(self.rules).to_string());
+    }
+
+    fn call(&self, name: &str, arg: i64, scope: &Scope) -> Result<Value, String> {
+        let clauses = &self.clauses[name];
+
+        let (param, body) = clauses
+            .iter()
+            .find(|(param, _)| param.parse() == Ok(arg))
+            .or_else(|| clauses.iter().find(|(param, _)| is_identifier(param)))
+            .ok_or(format!("{name}({arg}) is not defined"))?;
+
+        let mut scope = scope.clone();
+
+        if is_identifier(param) {
+            set_int(&mut scope, param, arg);
+        }
+
+        return self.term(body, &scope);
+    }
+
+    /// An index where `expr` is index arithmetic, otherwise a term.
+    fn value(&self, expr: &Expr, scope: &Scope) -> Result<Value, String> {
+        match expr {
+            Expr::Plain(src) => match int(src, &ints(scope)) {
+                Ok(n) => return Ok(Value::Number(Integer::from(n))),
+                Err(_) => return self.term(src, scope),
+            },
+            Expr::If(test, then, otherwise) => {
+                let branch = if condition(test, &ints(scope))? { then } else { otherwise };
+                return self.value(branch, scope);
+            }
+        }
+    }
+
+    /// Runs `statements`, returning the line of the `stop when` that ended
+    /// the script, if one did.
+    fn run(&self, statements: &[Statement], scope: &mut Scope, out: &mut dyn FnMut(String)) -> Result<Option<usize>, Failure> {
+        for statement in statements {
+            match statement {
+                Statement::Clause { .. } => {}
+                Statement::Let { line, name, expr } => {
+                    let value = self.value(expr, scope).map_err(|reason| (*line, reason))?;
+                    scope.set(name, value);
+                }
+                Statement::For { line, var, from, to, body } => {
+                    let ints = ints(scope);
+                    let from = int(from, &ints).map_err(|reason| (*line, reason))?;
+                    let to = int(to, &ints).map_err(|reason| (*line, reason))?;
+
+                    for n in from..to {
+                        set_int(scope, var, n);
+
+                        if let Some(stopped) = self.run(body, scope, out)? {
+                            return Ok(Some(stopped));
+                        }
+                    }
+                }
+                Statement::If { line, condition: test, then, otherwise } => {
+                    let branch = if condition(test, &ints(scope)).map_err(|reason| (*line, reason))? { then } else { otherwise };
+
+                    if let Some(stopped) = self.run(branch, scope, out)? {
+                        return Ok(Some(stopped));
+                    }
+                }
+                Statement::Stop { line, expr } => {
+                    if self.term(expr, scope).map_err(|reason| (*line, reason))? == Value::zero() {
+                        return Ok(Some(*line));
+                    }
+                }
+                Statement::Print { line, expr } => {
+                    let value = self.value(&Expr::Plain(expr.clone()), scope).map_err(|reason| (*line, reason))?;
+                    out(format!("{expr} = {}", AST::from_value(&value)));
+                }
+            }
+        }
+
+        return Ok(None);
+    }
+}
+
+fn clauses(statements: &[Statement], out: &mut HashMap<String, Vec<(String, String)>>) {
+    for statement in statements {
+        match statement {
+            Statement::Clause { name, param, body } => out.entry(name.clone()).or_default().push((param.clone(), body.clone())),
+            Statement::For { body, .. } => clauses(body, out),
+            Statement::If { then, otherwise, .. } => {
+                clauses(then, out);
+                clauses(otherwise, out);
+            }
+            Statement::Let { .. } | Statement::Stop { .. } | Statement::Print { .. } => {}
+        }
+    }
+}
+
+/// `src` built in `scope` the way a script builds a term, with generators
+/// numbered from 1, but not simplified.
+pub fn build(src: &str, scope: &Scope) -> Result<Value, String> {
+    return Context { rules: &vec![], clauses: HashMap::new(), offset: 0 }.build(src, scope);
+}
+
+/// What `let x = src` binds `x` to in a script: an index or a simplified
+/// term.
+pub fn value(src: &str, rules: &Vec<Rule>, scope: &Scope) -> Result<Value, String> {
+    return Context { rules, clauses: HashMap::new(), offset: 0 }.value(&Expr::Plain(src.to_string()), scope);
+}
+
+/// Runs the script `src` in `scope`, simplifying every term under `rules`
+/// and giving each line it prints to `out`. Generators are numbered from 1
+/// like the matrix, or from 0 if the script writes `E(0)`, `F(0)` or
+/// `H(0)` anywhere.
+///
+/// Returns the line of the `stop when` that ended the script, if one did.
+pub fn run(src: &str, rules: &Vec<Rule>, scope: &mut Scope, out: &mut dyn FnMut(String)) -> Result<Option<usize>, Failure> {
+    let lines = src
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim().trim_end_matches(';').trim_end().to_string()))
+        .collect::<Vec<(usize, String)>>();
+
+    let mut lines = Lines { lines, i: 0 };
+    let statements = lines.statements(false)?;
+
+    if let Some((number, line)) = lines.peek() {
+        return Err((*number, format!("unexpected `{line}`")));
+    }
+
+    let mut context = Context { rules, clauses: HashMap::new(), offset: offset(src) };
+    clauses(&statements, &mut context.clauses);
+
+    return context.run(&statements, scope, out);
+}
+
+/// Runs the script at `path`, giving what it prints to `out`. The verdict
+/// is where it stopped and with which indices, and holds if it ran without
+/// an error.
+pub fn report(path: &str, rules: &RuleSet, cartan: &Arc<CartanMatrix>, out: &mut dyn FnMut(String)) -> Result<Verdict, ProofError> {
+    let src = std::fs::read_to_string(path).map_err(|source| ProofError::Io { path: path.to_string(), source })?;
+    let mut scope = Scope::new(cartan);
+
+    match run(&src, rules, &mut scope, out) {
+        Ok(Some(line)) => {
+            let mut ints = ints(&scope).into_iter().collect::<Vec<(String, i64)>>();
+            ints.sort();
+
+            let with = ints.iter().map(|(name, n)| format!("{name} = {n}")).collect::<Vec<String>>();
+            return Ok(Verdict { holds: true, text: format!("{path}:{line}: stopped with {}\n", with.join(", ")) });
+        }
+        Ok(None) => return Ok(Verdict { holds: true, text: String::new() }),
+        Err((line, reason)) => return Ok(Verdict { holds: false, text: format!("{path}:{line}: {reason}\n") }),
+    }
+}
+
+///////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What a script printed, and the line it stopped on with the value of
+    /// `i` there, if it stopped.
+    type Ran = (Vec<String>, Option<(usize, i64)>);
+
+    fn run_in(src: &str, cartan: CartanMatrix) -> Result<Ran, Failure> {
+        let cartan = Arc::new(cartan);
+        let rules = RuleSet::load("./src/map", &cartan).unwrap();
+        let mut scope = Scope::new(&cartan);
+        let mut printed = vec![];
+
+        let stopped = run(src, &rules, &mut scope, &mut |line| printed.push(line))?;
+
+        return Ok((printed, stopped.map(|line| (line, ints(&scope)["i"]))));
+    }
+
+    fn matrix() -> CartanMatrix {
+        return CartanMatrix::load("./src/matrix").unwrap();
+    }
+
+    #[test]
+    fn lets_loops_and_branches() {
+        let src = "\
+let k = 2
+let j = k * 3 - 4
+for i in 1..4 {
+    if i == j {
+        print [E(i), F(i)]
+    } else {
+        print [E(1), E(i)]
+    }
+}
+let x = [E(1), E(2)]
+stop when [x, E(1)] == 0
+print x
+";
+        let (printed, stopped) = run_in(src, CartanMatrix::dynkin('A', 3).unwrap()).unwrap();
+
+        assert_eq!(printed, ["[E(1), E(i)] = 0", "[E(i), F(i)] = H(2)", "[E(1), E(i)] = 0"]);
+        assert_eq!(stopped, Some((11, 3)));
+    }
+
+    #[test]
+    fn n_f_runs_through_without_vanishing() {
+        let src = std::fs::read_to_string("src/n_f").unwrap();
+        let (printed, stopped) = run_in(&src, matrix()).unwrap();
+
+        assert_eq!(stopped, None);
+        assert_eq!(printed.len(), 8);
+        assert_eq!(printed[0], "nx_f = -E(2)");
+    }
+
+    #[test]
+    fn n_f_stops_where_n_vanishes_in_a2() {
+        // Along 1, 2, 1, ..., N = [[E(1), E(2)], E(1)] is a Serre relation.
+        let src = std::fs::read_to_string("src/n_f").unwrap().replace("i % 3 + 1", "i % 2 + 1");
+        let (printed, stopped) = run_in(&src, CartanMatrix::dynkin('A', 2).unwrap()).unwrap();
+
+        assert_eq!(printed, ["nx_f = -E(2)", "nx_f = 0"]);
+        assert_eq!(stopped, Some((22, 2)));
+    }
+
+    #[test]
+    fn malformed_scripts_point_at_their_line() {
+        let failure = |src: &str| run_in(src, matrix()).unwrap_err();
+
+        assert_eq!(failure("for i in 1..3 {\n    print E(i)\n"), (2, "expected `}`".to_string()));
+        assert_eq!(failure("let x = E(1)\nfrobnicate x\n"), (2, "expected a statement, got `frobnicate x`".to_string()));
+        assert_eq!(failure("if 1 == 1 {\n    print E(1)\n} else\n").0, 3);
+        assert_eq!(failure("let x = [E(1),\n").0, 1);
+        assert_eq!(failure("print E(4)\n").0, 1);
+    }
+}
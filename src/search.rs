@@ -0,0 +1,698 @@
+use std::fmt::Display;
+use std::ops::Add;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+
+use crate::cartan::CartanMatrix;
+use crate::error::ProofError;
+use crate::ring::Ring;
+use crate::v2::{Gen, Ideal, Value};
+
+/// `N = [...[[E(w1), E(w2)], E(w3)], ...]` for a word `w`, carried along
+/// with `[N, F(i)]` and `[N, H(i)]` for every generator so that growing the
+/// word by one letter only needs brackets with a single `E`.
+#[derive(Clone)]
+pub struct Walk<R: Ring> {
+    ring: R,
+    word: Vec<i32>,
+    nx: Value<R>,
+    nx_f: Vec<Value<R>>,
+    nx_h: Vec<Value<R>>,
+    /// Shared by every walk grown from this one.
+    ideal: Arc<Ideal<R>>,
+}
+
+impl<R: Ring> Walk<R> {
+    pub fn new(first: i32, cartan: &CartanMatrix, ring: &R) -> Result<Walk<R>, ProofError> {
+        let rank = cartan.rank() as i32;
+
+        if first < 1 || first > rank {
+            return Err(ProofError::OutOfRange { index: first, rank: cartan.rank() });
+        }
+
+        // nx_fi = [E(w1), F(i)] = δ H(w1)
+        let nx_f = (1..=rank)
+            .map(|i| if i == first { Value::gen(ring, Gen::H(first)) } else { Value::zero(ring) })
+            .collect();
+
+        // nx_hi = [E(w1), H(i)] = -C(i, w1) * E(w1)
+        let nx_h = (1..=rank)
+            .map(|i| Value::term(ring, -cartan.at(i, first), vec![ Gen::E(first) ]))
+            .collect();
+
+        let ideal = Arc::new(Ideal::new(cartan, ring));
+
+        return Ok(Walk { ring: ring.clone(), word: vec![first], nx: Value::gen(ring, Gen::E(first)), nx_f, nx_h, ideal });
+    }
+
+    /// Walks a whole word, which must not be empty.
+    pub fn along(word: &[i32], cartan: &CartanMatrix, ring: &R) -> Result<Walk<R>, ProofError> {
+        let (first, rest) = word.split_first().ok_or(ProofError::EmptyWord)?;
+
+        if let Some(e) = rest.iter().find(|e| **e < 1 || **e as usize > cartan.rank()) {
+            return Err(ProofError::OutOfRange { index: *e, rank: cartan.rank() });
+        }
+
+        let mut walk = Walk::new(*first, cartan, ring)?;
+
+        for e in rest {
+            walk.step(*e, cartan);
+        }
+
+        return Ok(walk);
+    }
+
+    /// `N -> [N, E(e)]`.
+    pub fn step(&mut self, e: i32, cartan: &CartanMatrix) {
+        let g = Gen::E(e);
+        let nx = self.nx.clone().braket_gen(g, cartan);
+
+        for i in 1..=cartan.rank() as i32 {
+            let b = i as usize - 1;
+
+            // [[nx, E(e)], F(i)] = [nx_fi, E(e)] + [nx, [E(e), F(i)]]
+            //                    = [nx_fi, E(e)] + δ nx_hi
+            let nx_f = std::mem::replace(&mut self.nx_f[b], Value::zero(&self.ring));
+            self.nx_f[b] = if e == i {
+                nx_f.braket_gen(g, cartan).add(self.nx_h[b].clone())
+            } else {
+                nx_f.braket_gen(g, cartan)
+            };
+
+            // [[nx, E(e)], H(i)] = [nx_hi, E(e)] - C(i, e) * [nx, E(e)]
+            let nx_h = std::mem::replace(&mut self.nx_h[b], Value::zero(&self.ring));
+            self.nx_h[b] = nx_h.braket_gen(g, cartan).add(nx.clone().times(-cartan.at(i, e)));
+        }
+
+        self.nx = nx;
+        self.word.push(e);
+    }
+
+    pub fn word(&self) -> &[i32] {
+        return &self.word;
+    }
+
+    /// Writes the walk as lines read back by `Walk::read`. Every value is
+    /// written exactly, so a walk read back goes on exactly as it would have.
+    pub fn write(&self) -> String {
+        let mut out = format!("walk {}\nnx {}\n", show_word(&self.word), self.nx.write());
+
+        for nx_f in &self.nx_f {
+            out += &format!("nx_f {}\n", nx_f.write());
+        }
+
+        for nx_h in &self.nx_h {
+            out += &format!("nx_h {}\n", nx_h.write());
+        }
+
+        return out;
+    }
+
+    pub fn read<'a>(lines: &mut impl Iterator<Item = &'a str>, cartan: &CartanMatrix, ring: &R) -> Option<Walk<R>> {
+        let rank = cartan.rank();
+
+        let word = read_word(field(lines.next()?, "walk")?)?;
+        let nx = Value::read(field(lines.next()?, "nx")?, rank, ring)?;
+        let nx_f = (0..rank)
+            .map(|_| Value::read(field(lines.next()?, "nx_f")?, rank, ring))
+            .collect::<Option<Vec<Value<R>>>>()?;
+        let nx_h = (0..rank)
+            .map(|_| Value::read(field(lines.next()?, "nx_h")?, rank, ring))
+            .collect::<Option<Vec<Value<R>>>>()?;
+
+        if word.iter().any(|e| *e < 1 || *e > rank as i32) {
+            return None;
+        }
+
+        return Some(Walk { ring: ring.clone(), word, nx, nx_f, nx_h, ideal: Arc::new(Ideal::new(cartan, ring)) });
+    }
+
+    /// What the walk found. Each `[N, F(i)]` only counts as zero once its
+    /// terms cancel, which is quick, but `N` is put in normal form whenever
+    /// they all do, so a candidate is never a word whose `N` is zero.
+    pub fn report(&self) -> Report {
+        let vanishes = self.nx_f.iter().all(|nx_f| nx_f.equals_zero());
+        return self.report_with(vanishes);
+    }
+
+    /// `report`, with every `[N, F(i)]` put in normal form as well. Exact,
+    /// but far too slow to run on every word of an enumeration.
+    pub fn exact_report(&self) -> Report {
+        let vanishes = self.nx_f.iter().all(|nx_f| nx_f.equals_zero() || nx_f.is_zero(&self.ideal));
+        return self.report_with(vanishes);
+    }
+
+    fn report_with(&self, vanishes: bool) -> Report {
+        // N is a bracket of E's, so it is zero if and only if every [N, F(i)] is.
+        return Report {
+            word: self.word.clone(),
+            zero: self.nx.equals_zero() || vanishes && self.nx.is_zero(&self.ideal),
+            vanishes,
+        };
+    }
+}
+
+/// What a walk found about its word.
+///
+/// A word with `vanishes` set but not `zero` is a candidate counter example:
+/// a non zero `N` killed by every `F(i)`. Both are exact for a candidate,
+/// but from `Walk::report` a word whose `[N, F(i)]` only cancel in normal
+/// form is passed over.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+    pub word: Vec<i32>,
+    pub zero: bool,
+    pub vanishes: bool,
+}
+
+impl Report {
+    pub fn is_candidate(&self) -> bool {
+        return self.vanishes && !self.zero;
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: ", show_word(&self.word))?;
+
+        if self.zero {
+            return write!(f, "N = 0");
+        }
+
+        if self.vanishes {
+            return write!(f, "[N, F(i)] = 0 for all i");
+        }
+
+        return write!(f, "[N, F(i)] != 0 for some i");
+    }
+}
+
+pub fn show_word(word: &[i32]) -> String {
+    return word.iter().map(|e| e.to_string()).collect::<Vec<String>>().join(",");
+}
+
+pub fn read_word(src: &str) -> Option<Vec<i32>> {
+    return src.split(',').map(|e| e.trim().parse::<i32>().ok()).collect();
+}
+
+///////
+
+/// Where a search reports to.
+pub trait Visitor<R: Ring> {
+    /// Called with the report of every word in order, returning whether to
+    /// go on.
+    fn visit(&mut self, report: &Report) -> bool;
+
+    /// Called between words with a way to build the progress so far, for a
+    /// visitor that wants to save it.
+    fn progress(&mut self, _progress: impl FnOnce() -> Progress<R>) {}
+}
+
+/// How far a search has got, enough to pick it up again exactly there.
+pub enum Progress<R: Ring> {
+    /// The next word of a sequence, not yet reported.
+    Periodic(Walk<R>),
+    /// The next task of a depth first search split at `depth`.
+    Words { depth: usize, next: usize },
+    /// The next level of a breadth first search, not yet reported.
+    BreadthFirst(Vec<Walk<R>>),
+}
+
+impl<R: Ring> Progress<R> {
+    /// Whether this is progress of the kind `search` makes.
+    pub fn fits(&self, search: &Search) -> bool {
+        return matches!(
+            (search, self),
+            (Search::Periodic(_), Progress::Periodic(_)) | (Search::Words(_), Progress::Words { .. }) | (Search::BreadthFirst(_), Progress::BreadthFirst(_))
+        );
+    }
+
+    /// The name `write` gives this kind of progress.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Progress::Periodic(_) => return "cycle",
+            Progress::Words { .. } => return "words",
+            Progress::BreadthFirst(_) => return "bfs",
+        }
+    }
+
+    /// Writes the progress as lines read back by `Progress::read`.
+    pub fn write(&self) -> String {
+        match self {
+            Progress::Periodic(walk) => return format!("progress {}\n{}", self.kind(), walk.write()),
+            Progress::Words { depth, next } => return format!("progress {} {depth} {next}\n", self.kind()),
+            Progress::BreadthFirst(level) => {
+                let walks = level.iter().map(|walk| walk.write()).collect::<String>();
+                return format!("progress {} {}\n{walks}", self.kind(), level.len());
+            }
+        }
+    }
+
+    pub fn read<'a>(lines: &mut impl Iterator<Item = &'a str>, cartan: &CartanMatrix, ring: &R) -> Option<Progress<R>> {
+        let line = field(lines.next()?, "progress")?.split_whitespace().collect::<Vec<&str>>();
+
+        match line[..] {
+            ["cycle"] => return Some(Progress::Periodic(Walk::read(lines, cartan, ring)?)),
+            ["words", depth, next] => return Some(Progress::Words { depth: depth.parse().ok()?, next: next.parse().ok()? }),
+            ["bfs", count] => {
+                let count = count.parse::<usize>().ok()?;
+                let level = (0..count).map(|_| Walk::read(lines, cartan, ring)).collect::<Option<Vec<Walk<R>>>>()?;
+                return Some(Progress::BreadthFirst(level));
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// The rest of `line` after the word `name`.
+pub fn field<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let rest = line.strip_prefix(name)?;
+
+    if rest.is_empty() {
+        return Some(rest);
+    }
+
+    return rest.strip_prefix(' ');
+}
+
+///////
+
+/// The words a search runs over.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Search {
+    /// A single word.
+    Word(Vec<i32>),
+    /// Every prefix of a pattern repeated forever, `1,2,3` for the old
+    /// `N(n) = [N(n - 1), E(n % 3 + 1)]`.
+    Periodic(Vec<i32>),
+    /// Every word up to a length, depth first in lexicographic order.
+    Words(usize),
+    /// Every word up to a length, shortest first.
+    BreadthFirst(usize),
+}
+
+impl Search {
+    /// The old search, cycling through every generator in turn.
+    pub fn cycle(cartan: &CartanMatrix) -> Search {
+        return Search::Periodic((1..=cartan.rank() as i32).collect());
+    }
+
+    /// Reports every word to `visitor`, for as long as it asks to go on,
+    /// starting over or from saved `progress` of the kind this search makes.
+    /// Words whose `N` is already zero are reported but not extended, since
+    /// every longer word through them is zero too.
+    ///
+    /// Enumerations are spread over `threads` threads, all cores for `0`.
+    /// Reports reach the visitor on the calling thread in the same order as
+    /// with one thread. A single word or sequence always runs on one thread.
+    pub fn run<R: Ring>(&self, cartan: &CartanMatrix, ring: &R, threads: usize, progress: Option<Progress<R>>, visitor: &mut impl Visitor<R>) -> Result<(), ProofError> {
+        let rank = cartan.rank() as i32;
+        let threads = if threads == 0 { available_threads() } else { threads };
+
+        if let Search::Word(word) | Search::Periodic(word) = self {
+            if word.is_empty() {
+                return Err(ProofError::EmptyWord);
+            }
+        }
+
+        if let Some(e) = self.letters().iter().find(|e| **e < 1 || **e > rank) {
+            return Err(ProofError::OutOfRange { index: *e, rank: cartan.rank() });
+        }
+
+        if let Some(progress) = progress.as_ref().filter(|progress| !progress.fits(self)) {
+            return Err(ProofError::Mismatch { what: self.to_string(), expected: "progress of its own kind", found: format!("{} progress", progress.kind()) });
+        }
+
+        match (self, progress) {
+            (Search::Word(word), _) => {
+                visitor.visit(&Walk::along(word, cartan, ring)?.exact_report());
+            }
+            (Search::Periodic(pattern), progress) => {
+                let mut walk = match progress {
+                    Some(Progress::Periodic(walk)) => walk,
+                    _ => Walk::new(pattern[0], cartan, ring)?,
+                };
+
+                loop {
+                    visitor.progress(|| Progress::Periodic(walk.clone()));
+
+                    let report = walk.report();
+
+                    if !visitor.visit(&report) || report.zero {
+                        return Ok(());
+                    }
+
+                    walk.step(pattern[walk.word().len() % pattern.len()], cartan);
+                }
+            }
+            (Search::Words(length), progress) => {
+                let (depth, next) = match progress {
+                    Some(Progress::Words { depth, next }) => (depth, next),
+                    _ => (split_depth(rank, threads), 0),
+                };
+
+                let mut tasks = vec![];
+
+                for e in 1..=rank {
+                    split(Walk::new(e, cartan, ring)?, depth, *length, cartan, &mut tasks);
+                }
+
+                // Subtrees are walked on every thread at once but reported in
+                // order, each through a channel with room for a few reports,
+                // so memory stays bounded and an early stop ends the search
+                // without walking every subtree first.
+                let todo = &tasks[next.min(tasks.len())..];
+                let (senders, receivers): (Vec<_>, Vec<_>) = todo
+                    .iter()
+                    .map(|_| {
+                        let (sender, receiver) = mpsc::sync_channel::<Report>(REPORTS_IN_FLIGHT);
+                        (Mutex::new(Some(sender)), receiver)
+                    })
+                    .unzip();
+                let taken = AtomicUsize::new(0);
+
+                std::thread::scope(|scope| {
+                    for _ in 0..threads.min(todo.len()) {
+                        scope.spawn(|| loop {
+                            let i = taken.fetch_add(1, Ordering::Relaxed);
+
+                            let Some(task) = todo.get(i) else {
+                                return;
+                            };
+
+                            // Sending fails once the search has stopped.
+                            let sender = senders[i].lock().unwrap().take().expect("Every task is taken once!");
+
+                            match task {
+                                Task::Done(report) => {
+                                    let _ = sender.send(report.clone());
+                                }
+                                Task::Subtree(walk) => {
+                                    depth_first(walk.clone(), *length, cartan, &mut |report| sender.send(report.clone()).is_ok());
+                                }
+                            }
+                        });
+                    }
+
+                    for (i, receiver) in receivers.into_iter().enumerate() {
+                        visitor.progress(|| Progress::Words { depth, next: next + i });
+
+                        for report in receiver {
+                            if !visitor.visit(&report) {
+                                return;
+                            }
+                        }
+                    }
+                });
+            }
+            (Search::BreadthFirst(length), progress) => {
+                let mut level = match progress {
+                    Some(Progress::BreadthFirst(level)) => level,
+                    _ => (1..=rank).map(|e| Walk::new(e, cartan, ring)).collect::<Result<Vec<Walk<R>>, ProofError>>()?,
+                };
+
+                while !level.is_empty() {
+                    visitor.progress(|| Progress::BreadthFirst(level.clone()));
+
+                    let steps = parallel_map(&level, threads, |walk| {
+                        let report = walk.report();
+
+                        if report.zero || walk.word().len() >= *length {
+                            return (report, vec![]);
+                        }
+
+                        let next = (1..=rank)
+                            .map(|e| {
+                                let mut next = walk.clone();
+                                next.step(e, cartan);
+                                next
+                            })
+                            .collect::<Vec<Walk<R>>>();
+
+                        return (report, next);
+                    });
+
+                    level = vec![];
+
+                    for (report, next) in steps {
+                        if !visitor.visit(&report) {
+                            return Ok(());
+                        }
+
+                        level.extend(next);
+                    }
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// The generators a single word or sequence is made of, none for an
+    /// enumeration.
+    pub fn letters(&self) -> &[i32] {
+        match self {
+            Search::Word(word) | Search::Periodic(word) => word,
+            Search::Words(_) | Search::BreadthFirst(_) => &[],
+        }
+    }
+}
+
+/// A piece of a depth first search: a word that is only reported, or a
+/// whole subtree of words to walk.
+enum Task<R: Ring> {
+    Done(Report),
+    Subtree(Walk<R>),
+}
+
+/// How many reports a subtree can get ahead of the visitor by.
+const REPORTS_IN_FLIGHT: usize = 256;
+
+/// How deep to split the tree of words into independent subtrees, deep
+/// enough that every thread has several to pick from.
+fn split_depth(rank: i32, threads: usize) -> usize {
+    let mut depth = 1;
+    let mut subtrees = rank as usize;
+
+    while rank > 1 && subtrees < threads * 16 {
+        depth += 1;
+        subtrees *= rank as usize;
+    }
+
+    return depth;
+}
+
+/// Splits the words below `walk` into tasks, in depth first order.
+fn split<R: Ring>(walk: Walk<R>, depth: usize, length: usize, cartan: &CartanMatrix, tasks: &mut Vec<Task<R>>) {
+    if walk.word().len() >= depth.min(length) {
+        tasks.push(Task::Subtree(walk));
+        return;
+    }
+
+    let report = walk.report();
+    let zero = report.zero;
+
+    tasks.push(Task::Done(report));
+
+    if zero {
+        return;
+    }
+
+    for e in 1..=cartan.rank() as i32 {
+        let mut next = walk.clone();
+        next.step(e, cartan);
+        split(next, depth, length, cartan, tasks);
+    }
+}
+
+fn depth_first<R: Ring>(walk: Walk<R>, length: usize, cartan: &CartanMatrix, visit: &mut impl FnMut(&Report) -> bool) -> bool {
+    let report = walk.report();
+
+    if !visit(&report) {
+        return false;
+    }
+
+    if report.zero || walk.word().len() >= length {
+        return true;
+    }
+
+    for e in 1..=cartan.rank() as i32 {
+        let mut next = walk.clone();
+        next.step(e, cartan);
+
+        if !depth_first(next, length, cartan, visit) {
+            return false;
+        }
+    }
+
+    return true;
+}
+
+///////
+
+pub fn available_threads() -> usize {
+    return std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+}
+
+/// `items.iter().map(f)` on `threads` threads, with the results in order.
+///
+/// Threads take the next item off a shared counter rather than a fixed share
+/// each, so one slow item does not leave the other threads idle.
+pub fn parallel_map<T: Sync, U: Send>(items: &[T], threads: usize, f: impl Fn(&T) -> U + Sync) -> Vec<U> {
+    if threads <= 1 || items.len() <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let f = &f;
+
+    let done = std::thread::scope(|scope| {
+        let workers = (0..threads.min(items.len()))
+            .map(|_| scope.spawn(|| {
+                let mut done = vec![];
+
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+
+                    if i >= items.len() {
+                        return done;
+                    }
+
+                    done.push((i, f(&items[i])));
+                }
+            }))
+            .collect::<Vec<_>>();
+
+        return workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("Search thread panicked!"))
+            .collect::<Vec<(usize, U)>>();
+    });
+
+    let mut out = (0..items.len()).map(|_| None).collect::<Vec<Option<U>>>();
+
+    for (i, u) in done {
+        out[i] = Some(u);
+    }
+
+    return out.into_iter().map(|u| u.expect("Every item is mapped once!")).collect();
+}
+
+impl FromStr for Search {
+    type Err = ();
+
+    /// Parses `word:1,2,1,3`, `cycle:1,2,3`, `all:8` or `bfs:8`.
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let (mode, arg) = src.trim().split_once(':').ok_or(())?;
+
+        let word = || read_word(arg).ok_or(());
+
+        match mode {
+            "word" => return Ok(Search::Word(word()?)),
+            "cycle" => return Ok(Search::Periodic(word()?)),
+            "all" => return Ok(Search::Words(arg.trim().parse().map_err(|_| ())?)),
+            "bfs" => return Ok(Search::BreadthFirst(arg.trim().parse().map_err(|_| ())?)),
+            _ => return Err(()),
+        }
+    }
+}
+
+impl Display for Search {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Search::Word(word) => write!(f, "word:{}", show_word(word)),
+            Search::Periodic(pattern) => write!(f, "cycle:{}", show_word(pattern)),
+            Search::Words(length) => write!(f, "all:{length}"),
+            Search::BreadthFirst(length) => write!(f, "bfs:{length}"),
+        }
+    }
+}
+
+///////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ring::Integers;
+
+    fn matrix() -> CartanMatrix {
+        return CartanMatrix::new(vec![vec![2, -1, -1], vec![-1, 2, -2], vec![-1, -1, 2]]).unwrap();
+    }
+
+    #[test]
+    fn words_whose_n_only_cancels_in_normal_form_are_not_candidates() {
+        let cartan = matrix();
+
+        for word in ["1,2,3,3,3", "2,3,2,3", "3,2,2,3,2", "1,3,2,2,2,2,3,3,3,3"] {
+            let walk = Walk::along(&read_word(word).unwrap(), &cartan, &Integers).unwrap();
+
+            assert_eq!(walk.report(), Report { word: walk.word().to_vec(), zero: true, vanishes: true }, "{word}");
+        }
+
+        let walk = Walk::along(&[1, 2, 3], &cartan, &Integers).unwrap();
+        assert_eq!(walk.exact_report(), Report { word: vec![1, 2, 3], zero: false, vanishes: false });
+    }
+
+    #[test]
+    fn words_read_back_as_written() {
+        assert_eq!(read_word("1,2, 1,3"), Some(vec![1, 2, 1, 3]));
+        assert_eq!(read_word(&show_word(&[3, 1, 2])), Some(vec![3, 1, 2]));
+        assert_eq!(read_word("1,,2"), None);
+        assert_eq!(read_word("1,x"), None);
+        assert_eq!(read_word("all:8"), None);
+    }
+
+    #[test]
+    fn searches_read_back_as_written() {
+        for search in [Search::Word(vec![1, 2, 1, 3]), Search::Periodic(vec![1, 2, 3]), Search::Words(8), Search::BreadthFirst(8)] {
+            assert_eq!(search.to_string().parse::<Search>(), Ok(search.clone()));
+        }
+
+        assert_eq!(" all: 5 ".parse::<Search>(), Ok(Search::Words(5)));
+
+        for src in ["1,2,3", "every:3", "all:x", "bfs:-1", "cycle:1,,2", "word"] {
+            assert_eq!(src.parse::<Search>(), Err(()), "{src}");
+        }
+    }
+
+    /// Every report, in the order the visitor got them.
+    struct Reports(Vec<Report>);
+
+    impl<R: Ring> Visitor<R> for Reports {
+        fn visit(&mut self, report: &Report) -> bool {
+            self.0.push(report.clone());
+            return true;
+        }
+    }
+
+    fn reports(search: &Search, cartan: &CartanMatrix, threads: usize) -> Vec<Report> {
+        let mut reports = Reports(vec![]);
+        search.run(cartan, &Integers, threads, None, &mut reports).unwrap();
+        return reports.0;
+    }
+
+    #[test]
+    fn threads_do_not_change_the_report_order() {
+        let cartan = matrix();
+
+        for search in [Search::Words(6), Search::BreadthFirst(6)] {
+            let one = reports(&search, &cartan, 1);
+
+            assert!(one.len() > 100);
+            assert_eq!(one, reports(&search, &cartan, 4), "{search}");
+        }
+    }
+
+    #[test]
+    fn enumerations_stop_at_their_length() {
+        let cartan = CartanMatrix::dynkin('A', 3).unwrap();
+
+        for length in 1..6 {
+            let words = reports(&Search::Words(length), &cartan, 2);
+
+            assert!(words.iter().all(|report| report.word.len() <= length));
+            assert!(words.iter().any(|report| report.word.len() == length));
+        }
+    }
+}
@@ -0,0 +1,318 @@
+use std::fmt::Display;
+
+use crate::confluence::{oriented, Oriented};
+use crate::integer::Integer;
+use crate::parser::{Rule, AST};
+use crate::value::*;
+
+/// The operators of a rule, which a precedence puts in some order. Generators
+/// come below all of them and numbers below those.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Op {
+    Braket,
+    Mul,
+    Negative,
+    Add,
+    C,
+}
+
+const OPS: [Op; 5] = [Op::Braket, Op::Mul, Op::Negative, Op::Add, Op::C];
+
+impl Display for Op {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Op::Braket => write!(f, "[,]"),
+            Op::Mul => write!(f, "*"),
+            Op::Negative => write!(f, "-"),
+            Op::Add => write!(f, "+"),
+            Op::C => write!(f, "C"),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+enum Symbol {
+    Op(Op),
+    Generator(String),
+    Number(Integer),
+}
+
+#[derive(Clone, PartialEq, Eq)]
+enum Term {
+    Var(String),
+    App(Symbol, Vec<Term>),
+}
+
+fn term(ast: &AST) -> Term {
+    let app = |op, args: &[&AST]| Term::App(Symbol::Op(op), args.iter().map(|arg| term(arg)).collect());
+
+    match ast {
+        AST::Named(name) => return Term::Var(name.clone()),
+        AST::Kind(name, index) => return Term::App(Symbol::Generator(name.clone()), vec![Term::Var(index.clone())]),
+        AST::Value(Value::Kind(name, index)) => {
+            let index = Term::App(Symbol::Number(Integer::from(*index)), vec![]);
+            return Term::App(Symbol::Generator(name.clone()), vec![index]);
+        }
+        AST::Value(Value::Number(n)) => return Term::App(Symbol::Number(n.clone()), vec![]),
+        AST::Value(value) => return term(&AST::from_value(value)),
+        AST::Braket(a, b) => return app(Op::Braket, &[a, b]),
+        AST::Add(a, b) => return app(Op::Add, &[a, b]),
+        AST::Mul(a, b) => return app(Op::Mul, &[a, b]),
+        AST::C(a, b) => return app(Op::C, &[a, b]),
+        AST::Negative(a) => return app(Op::Negative, &[a]),
+    }
+}
+
+fn occurs(name: &str, term: &Term) -> bool {
+    match term {
+        Term::Var(var) => return var == name,
+        Term::App(_, args) => return args.iter().any(|arg| occurs(name, arg)),
+    }
+}
+
+///////
+
+/// A lexicographic path ordering: operators ordered from greatest to
+/// least, each comparing its arguments left to right unless listed in
+/// `reversed`.
+struct Precedence {
+    ops: Vec<Op>,
+    reversed: Vec<Op>,
+}
+
+impl Precedence {
+    fn above(&self, f: &Symbol, g: &Symbol) -> bool {
+        match (f, g) {
+            (Symbol::Op(f), Symbol::Op(g)) => {
+                let rank = |op| self.ops.iter().position(|o| o == op);
+                return rank(f) < rank(g);
+            }
+            (Symbol::Op(_), _) => return true,
+            (_, Symbol::Op(_)) => return false,
+            (Symbol::Generator(f), Symbol::Generator(g)) => return f > g,
+            (Symbol::Generator(_), _) => return true,
+            (_, Symbol::Generator(_)) => return false,
+            (Symbol::Number(n), Symbol::Number(m)) => return n > m,
+        }
+    }
+
+    fn greater(&self, s: &Term, t: &Term) -> bool {
+        let Term::App(f, ss) = s else {
+            return false;
+        };
+
+        let Term::App(g, ts) = t else {
+            return match t {
+                Term::Var(name) => occurs(name, s),
+                Term::App(_, _) => unreachable!(),
+            };
+        };
+
+        if ss.iter().any(|si| si == t || self.greater(si, t)) {
+            return true;
+        }
+
+        if !ts.iter().all(|tj| self.greater(s, tj)) {
+            return false;
+        }
+
+        if f != g {
+            return self.above(f, g);
+        }
+
+        let mut pairs = ss.iter().zip(ts.iter()).collect::<Vec<(&Term, &Term)>>();
+
+        if matches!(f, Symbol::Op(op) if self.reversed.contains(op)) {
+            pairs.reverse();
+        }
+
+        return match pairs.into_iter().find(|(si, ti)| si != ti) {
+            Some((si, ti)) => self.greater(si, ti),
+            None => false,
+        };
+    }
+
+    fn orients(&self, rule: &Oriented) -> bool {
+        return self.greater(&term(&rule.pattern), &term(&rule.expr));
+    }
+}
+
+impl Display for Precedence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ops = self.ops.iter().map(|op| op.to_string()).collect::<Vec<String>>();
+        write!(f, "{}", ops.join(" > "))?;
+
+        if !self.reversed.is_empty() {
+            let ops = self.reversed.iter().map(|op| op.to_string()).collect::<Vec<String>>();
+            write!(f, ", comparing the arguments of {} right to left", ops.join(" and "))?;
+        }
+
+        return Ok(());
+    }
+}
+
+fn permutations(ops: &[Op]) -> Vec<Vec<Op>> {
+    if ops.is_empty() {
+        return vec![vec![]];
+    }
+
+    let mut out = vec![];
+
+    for (i, op) in ops.iter().enumerate() {
+        let rest = [&ops[..i], &ops[i + 1..]].concat();
+
+        for mut tail in permutations(&rest) {
+            tail.insert(0, *op);
+            out.push(tail);
+        }
+    }
+
+    return out;
+}
+
+/// Every precedence of the operators, with every choice of which compare
+/// their arguments right to left.
+fn precedences() -> Vec<Precedence> {
+    let mut out = vec![];
+
+    for ops in permutations(&OPS) {
+        for mask in 0..1 << OPS.len() {
+            let reversed = OPS.iter().enumerate().filter(|(i, _)| mask & 1 << i != 0).map(|(_, op)| *op).collect();
+            out.push(Precedence { ops: ops.clone(), reversed });
+        }
+    }
+
+    return out;
+}
+
+/// What `report` found: the ordering that orients the most rules, and a
+/// warning for each rule it does not.
+#[derive(Clone, Debug)]
+pub struct Termination {
+    pub precedence: String,
+    pub warnings: Vec<String>,
+    pub oriented: usize,
+    pub rules: usize,
+}
+
+impl Termination {
+    /// Whether the ordering orients every rule.
+    pub fn terminates(&self) -> bool {
+        return self.oriented == self.rules;
+    }
+}
+
+impl Display for Termination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Path ordering with {}", self.precedence)?;
+
+        for warning in &self.warnings {
+            writeln!(f, "warning: {warning}")?;
+        }
+
+        return writeln!(f, "{} of {} rules oriented", self.oriented, self.rules);
+    }
+}
+
+/// The precedence that orients the most of `variants`, and the rules it
+/// does not orient.
+fn best(variants: &[Oriented]) -> (Precedence, Vec<usize>) {
+    let unoriented = |precedence: &Precedence| {
+        let mut out = variants.iter().filter(|variant| !precedence.orients(variant)).map(|variant| variant.rule).collect::<Vec<usize>>();
+        out.dedup();
+        return out;
+    };
+
+    return precedences()
+        .into_iter()
+        .map(|precedence| {
+            let failed = unoriented(&precedence);
+            return (precedence, failed);
+        })
+        .min_by_key(|(_, failed)| failed.len())
+        .unwrap();
+}
+
+/// Looks for a path ordering in which every rule, tried either way round
+/// the way `simplify` tries it, makes its term smaller. Rules that shrink
+/// terms in some ordering cannot rewrite forever, so if one orients every
+/// rule, rewriting terminates; AC matching of sums is not accounted for.
+pub fn report(rules: &[Rule]) -> Termination {
+    let variants = oriented(rules);
+    let (precedence, failed) = best(&variants);
+
+    let warnings = variants
+        .iter()
+        .filter(|variant| !precedence.orients(variant))
+        .map(|variant| {
+            let rule = &rules[variant.rule];
+
+            if variant.flipped {
+                let tried = Rule::new(variant.pattern.clone(), variant.expr.clone());
+                return format!("cannot orient {rule} matched the other way round, as {tried}");
+            }
+
+            return format!("cannot orient {rule}");
+        })
+        .collect();
+
+    return Termination { precedence: precedence.to_string(), warnings, oriented: rules.len() - failed.len(), rules: rules.len() };
+}
+
+/// The path ordering `report` settles on, for orienting new rules the same
+/// way as the old.
+pub(crate) struct Ordering(Precedence);
+
+impl Ordering {
+    /// `a = b` as a rule from the greater side to the other, or None if
+    /// neither side is greater.
+    pub(crate) fn orient(&self, a: &AST, b: &AST) -> Option<Rule> {
+        if self.0.greater(&term(a), &term(b)) {
+            return Some(Rule::new(a.clone(), b.clone()));
+        }
+
+        if self.0.greater(&term(b), &term(a)) {
+            return Some(Rule::new(b.clone(), a.clone()));
+        }
+
+        return None;
+    }
+}
+
+pub(crate) fn ordering(rules: &[Rule]) -> Ordering {
+    return Ordering(best(&oriented(rules)).0);
+}
+
+///////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn rules(src: &[(&str, &str)]) -> Vec<Rule> {
+        return src.iter().map(|(pattern, expr)| Rule::new(parse(pattern).unwrap(), parse(expr).unwrap())).collect();
+    }
+
+    #[test]
+    fn shrinking_rules_are_oriented() {
+        let termination = report(&rules(&[("--a", "a"), ("a + 0", "a"), ("[a, a]", "0"), ("2 * a", "a + a")]));
+
+        assert!(termination.terminates(), "{termination}");
+        assert!(termination.warnings.is_empty());
+        assert_eq!((termination.oriented, termination.rules), (4, 4));
+    }
+
+    #[test]
+    fn antisymmetry_is_not_oriented_both_ways_round() {
+        let termination = report(&rules(&[("-[a, b]", "[b, a]")]));
+        assert!(termination.terminates(), "{termination}");
+
+        // Either precedence of - and [,] orients only one of the two.
+        let termination = report(&rules(&[("-[a, b]", "[b, a]"), ("[a, b]", "-[b, a]")]));
+
+        assert!(!termination.terminates());
+        assert_eq!((termination.oriented, termination.rules), (1, 2));
+        assert!(termination.warnings.iter().all(|warning| warning.starts_with("cannot orient [a, b] = -[b, a]")), "{termination}");
+    }
+}
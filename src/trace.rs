@@ -0,0 +1,187 @@
+use std::fmt::Display;
+
+use crate::error::ProofError;
+use crate::parser::AST;
+use crate::value::*;
+
+/// What a step did to its subterm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Applied the rule with this index.
+    Rule(usize),
+    /// Added or multiplied two numbers, or moved a scalar to the left of a
+    /// product.
+    Arithmetic,
+    /// Collected terms, rebracketing in the Lyndon basis.
+    Collect,
+}
+
+/// One rewrite of a subterm.
+#[derive(Clone, Debug)]
+pub struct Step {
+    pub action: Action,
+    /// The path from the root to the subterm, 0 for the left or only child
+    /// and 1 for the right.
+    pub position: Vec<usize>,
+    /// What the rule's variables were bound to.
+    pub substitution: Vec<(String, Value)>,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// How a term was simplified, one step at a time, so that the result can
+/// be checked by hand.
+#[derive(Clone, Debug)]
+pub struct Trace {
+    pub start: Value,
+    pub steps: Vec<Step>,
+    /// The term the steps end in, with its terms collected.
+    pub result: Value,
+}
+
+fn show(value: &Value) -> String {
+    return AST::from_value(value).to_string();
+}
+
+pub fn show_position(position: &[usize]) -> String {
+    if position.is_empty() {
+        return "root".to_string();
+    }
+
+    return position.iter().map(|i| i.to_string()).collect::<Vec<String>>().join(".");
+}
+
+/// `value` with the subterm at `position` replaced by `by`.
+pub fn replace_at(value: &Value, position: &[usize], by: &Value) -> Option<Value> {
+    let Some((i, rest)) = position.split_first() else {
+        return Some(by.clone());
+    };
+
+    let child = |a: &Value| replace_at(a, rest, by).map(Box::new);
+
+    match (value, i) {
+        (Value::Braket(a, b), 0) => return Some(Value::Braket(child(a)?, b.clone())),
+        (Value::Braket(a, b), 1) => return Some(Value::Braket(a.clone(), child(b)?)),
+        (Value::Add(a, b), 0) => return Some(Value::Add(child(a)?, b.clone())),
+        (Value::Add(a, b), 1) => return Some(Value::Add(a.clone(), child(b)?)),
+        (Value::Mul(a, b), 0) => return Some(Value::Mul(child(a)?, b.clone())),
+        (Value::Mul(a, b), 1) => return Some(Value::Mul(a.clone(), child(b)?)),
+        (Value::Negative(a), 0) => return Some(Value::Negative(child(a)?)),
+        _ => return None,
+    }
+}
+
+impl Trace {
+    /// The whole term after each step, one per line, the way the files in
+    /// `docs` write a derivation.
+    pub fn derivation(&self) -> Result<String, ProofError> {
+        let mut term = self.start.clone();
+        let mut lines = vec![show(&term)];
+
+        for step in &self.steps {
+            term = replace_at(&term, &step.position, &step.after)
+                .ok_or_else(|| ProofError::NoSubterm { position: show_position(&step.position), term: show(&term) })?;
+            lines.push(show(&term));
+        }
+
+        if self.result != term {
+            lines.push(show(&self.result));
+        }
+
+        lines.dedup();
+
+        return Ok(lines.join("\n") + "\n");
+    }
+}
+
+/// Several derivations in one file, each after a `...` line.
+pub fn derivations(traces: &[Trace]) -> Result<String, ProofError> {
+    let derivations = traces
+        .iter()
+        .map(|trace| Ok(format!("...\n\n{}", trace.derivation()?)))
+        .collect::<Result<Vec<String>, ProofError>>()?;
+
+    return Ok(derivations.join("\n"));
+}
+
+/// Every step with the rule applied, where, with what and to what, between
+/// the term it starts from and the result.
+impl Display for Trace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "start {}", show(&self.start))?;
+
+        for step in &self.steps {
+            match step.action {
+                Action::Rule(rule) => write!(f, "rule {rule}")?,
+                Action::Arithmetic => write!(f, "arithmetic")?,
+                Action::Collect => write!(f, "collect")?,
+            }
+
+            write!(f, " at {}", show_position(&step.position))?;
+
+            if !step.substitution.is_empty() {
+                let bindings = step.substitution.iter().map(|(name, value)| format!("{name} = {}", show(value)));
+                write!(f, " with {}", bindings.collect::<Vec<String>>().join(", "))?;
+            }
+
+            writeln!(f)?;
+            writeln!(f, "    {}", show(&step.before))?;
+            writeln!(f, "    {}", show(&step.after))?;
+        }
+
+        return writeln!(f, "result {}", show(&self.result));
+    }
+}
+
+///////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartan::CartanMatrix;
+    use crate::{simplify_traced, str_build, RuleSet, Scope};
+    use std::sync::Arc;
+
+    fn traced(src: &str) -> Trace {
+        let cartan = Arc::new(CartanMatrix::load("./src/matrix").unwrap());
+        let rules = RuleSet::load("./src/map", &cartan).unwrap();
+
+        return simplify_traced(str_build(src, &cartan).unwrap(), &rules, &Scope::new(&cartan)).unwrap();
+    }
+
+    #[test]
+    fn steps_replay_to_the_result() {
+        let trace = traced("[[E(1), E(2)], [F(1), F(2)]]");
+        let mut term = trace.start.clone();
+        assert!(trace.steps.len() > 3);
+
+        for step in &trace.steps {
+            term = replace_at(&term, &step.position, &step.after).unwrap();
+        }
+
+        assert_eq!(show(&term), show(&trace.result));
+    }
+
+    #[test]
+    fn derivations_run_from_start_to_result() {
+        let trace = traced("[[E(1), E(2)], [F(1), F(2)]]");
+        let derivation = trace.derivation().unwrap();
+        let lines = derivation.lines().collect::<Vec<&str>>();
+
+        assert_eq!(lines.first(), Some(&show(&trace.start).as_str()));
+        assert_eq!(lines.last(), Some(&show(&trace.result).as_str()));
+        assert!(lines.len() > 2 && lines.windows(2).all(|pair| pair[0] != pair[1]));
+
+        assert_eq!(derivations(&[trace.clone(), trace]).unwrap(), format!("...\n\n{derivation}\n...\n\n{derivation}"));
+    }
+
+    #[test]
+    fn positions_name_a_path_from_the_root() {
+        let value = brak(e(1), Value::Negative(Box::new(e(2))));
+
+        assert_eq!(show_position(&[]), "root");
+        assert_eq!(show_position(&[1, 0]), "1.0");
+        assert_eq!(show(&replace_at(&value, &[1, 0], &e(3)).unwrap()), "[E(1), -E(3)]");
+        assert_eq!(replace_at(&value, &[0, 0], &e(3)), None);
+    }
+}
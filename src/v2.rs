@@ -1,60 +1,550 @@
-///        
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::ops::Add;
+use std::sync::RwLock;
+
+use crate::cartan::CartanMatrix;
+use crate::error::ProofError;
+use crate::lyndon::{self, Poly};
+use crate::ring::Ring;
+use crate::value;
+
+/// A Chevalley generator `E(i)`, `F(i)` or `H(i)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Gen {
+    E(i32),
+    F(i32),
+    H(i32),
+}
+
+impl Gen {
+    pub fn index(&self) -> i32 {
+        match self {
+            Gen::E(i) | Gen::F(i) | Gen::H(i) => *i,
+        }
+    }
+
+    fn kind(&self) -> Kind {
+        match self {
+            Gen::E(_) => Kind::E,
+            Gen::F(_) => Kind::F,
+            Gen::H(_) => Kind::H,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Kind {
+    E,
+    F,
+    H,
+}
+
+impl Kind {
+    fn gen(&self, i: i32) -> Gen {
+        match self {
+            Kind::E => Gen::E(i),
+            Kind::F => Gen::F(i),
+            Kind::H => Gen::H(i),
+        }
+    }
+}
+
+impl Display for Gen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Gen::E(i) => write!(f, "E({i})"),
+            Gen::F(i) => write!(f, "F({i})"),
+            Gen::H(i) => write!(f, "H({i})"),
+        }
+    }
+}
+
+///////
+
+/// `n * [..[es0, es1].., esk]`, kept in one of three shapes: a left-normed
+/// bracket of E's only, of F's only, or a single H. Every element of the
+/// algebra is a sum of such terms, and `braket` keeps them in that shape.
 #[derive(Clone, Eq, PartialEq)]
-struct Term {
-    n: i32,
+struct Term<R: Ring> {
+    n: R::Elem,
+    kind: Kind,
     es: Vec<i32>,
 }
 
-impl Term {
-    fn new(n: i32, es: Vec<i32>) -> Term {
-        return Term { n, es };
+impl<R: Ring> Term<R> {
+    fn new(n: R::Elem, es: Vec<Gen>) -> Term<R> {
+        let kind = es.first().expect("Terms are never empty!").kind();
+
+        if es.iter().any(|g| g.kind() != kind) || (kind == Kind::H && es.len() != 1) {
+            panic!("Terms are brackets of E's only, of F's only, or a single H!");
+        }
+
+        return Term { n, kind, es: es.iter().map(|g| g.index()).collect() };
+    }
+
+    fn gens(&self) -> impl Iterator<Item = Gen> + '_ {
+        return self.es.iter().map(|i| self.kind.gen(*i));
+    }
+
+    fn last(&self) -> Gen {
+        return self.kind.gen(*self.es.last().expect("Terms are never empty!"));
     }
 }
 
+///////
+
+/// A sum of terms over the coefficient ring `R`.
 ///
+/// Like terms are merged and the two letters of a length two bracket are
+/// kept in a fixed order, so an empty sum is always zero; a non empty one can
+/// still be zero through the Jacobi identity.
 #[derive(Clone, Eq, PartialEq)]
-struct Value {
-    terms: Vec<Term>
+pub struct Value<R: Ring> {
+    ring: R,
+    terms: Vec<Term<R>>
 }
 
-impl Value {
-    fn new(terms: Vec<Term>) -> Value {
-        Value { terms }
+impl<R: Ring> Value<R> {
+    pub fn zero(ring: &R) -> Value<R> {
+        Value { ring: ring.clone(), terms: vec![] }
+    }
+
+    /// A single term `n * [..[es0, es1].., esk]`.
+    pub fn term(ring: &R, n: i32, es: Vec<Gen>) -> Value<R> {
+        Value::zero(ring).add(Value { ring: ring.clone(), terms: vec![Term::new(ring.lift_i32(n), es)] })
+    }
+
+    pub fn gen(ring: &R, g: Gen) -> Value<R> {
+        Value::term(ring, 1, vec![g])
+    }
+
+    /// Converts a built expression of generators, brackets, sums and integer
+    /// multiples, so derivations written for the rule engine can run here.
+    pub fn from_value(value: &value::Value, ring: &R, cartan: &CartanMatrix) -> Option<Value<R>> {
+        match value {
+            value::Value::Kind(name, i) => match name.as_str() {
+                "E" => Some(Value::gen(ring, Gen::E(*i))),
+                "F" => Some(Value::gen(ring, Gen::F(*i))),
+                "H" => Some(Value::gen(ring, Gen::H(*i))),
+                _ => None,
+            },
+            value::Value::Number(n) if n.is_zero() => Some(Value::zero(ring)),
+            value::Value::Braket(a, b) => Some(Value::from_value(a, ring, cartan)?.braket(&Value::from_value(b, ring, cartan)?, cartan)),
+            value::Value::Add(a, b) => Some(Value::from_value(a, ring, cartan)?.add(Value::from_value(b, ring, cartan)?)),
+            value::Value::Negative(a) => Some(Value::from_value(a, ring, cartan)?.times(-1)),
+            // Scalar products commute, so the number can be on either side.
+            value::Value::Mul(a, b) => {
+                let (n, a) = match (a.into_number(), b.into_number()) {
+                    (Some(n), _) => (n, b),
+                    (None, Some(n)) => (n, a),
+                    (None, None) => return None,
+                };
+
+                Some(Value::from_value(a, ring, cartan)?.scale(&ring.lift(&n)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Converts back into an expression, for printing or for the rule engine.
+    /// Fails on a coefficient that is not an integer.
+    pub fn to_value(&self) -> Result<value::Value, ProofError> {
+        let terms = self.terms.iter()
+            .map(|term| {
+                let e = term.gens()
+                    .map(|g| match g {
+                        Gen::E(i) => value::e(i),
+                        Gen::F(i) => value::f(i),
+                        Gen::H(i) => value::h(i),
+                    })
+                    .reduce(value::brak)
+                    .ok_or(ProofError::EmptyWord)?;
+
+                let n = self.ring.to_integer(&term.n).ok_or_else(|| ProofError::NotInteger(term.n.to_string()))?;
+
+                if n.is_one() {
+                    return Ok(e);
+                } else if n.is_negative() {
+                    return Ok(value::Value::Negative(Box::new(value::Value::Mul(Box::new(value::Value::Number(-n)), Box::new(e)))));
+                } else {
+                    return Ok(value::Value::Mul(Box::new(value::Value::Number(n)), Box::new(e)));
+                }
+            })
+            .collect::<Result<Vec<value::Value>, ProofError>>()?;
+
+        return Ok(terms.into_iter()
+            .reduce(|a, b| value::Value::Add(Box::new(a), Box::new(b)))
+            .unwrap_or(value::Value::zero()));
     }
 }
 
-impl Value {
-    fn braket(mut self, e: i32) -> Value {
-        for term in &mut self.terms {
-            term.es.push(e);
+impl<R: Ring> Value<R> {
+    /// `[self, g]` for a single generator.
+    pub fn braket_gen(mut self, g: Gen, cartan: &CartanMatrix) -> Value<R> {
+        let ring = self.ring.clone();
+
+        // Growing every term by the same letter keeps distinct terms distinct,
+        // so the common case needs no merging.
+        if !matches!(g, Gen::H(_)) && self.terms.iter().all(|term| term.kind == g.kind()) {
+            self.terms = self.terms
+                .into_iter()
+                .filter_map(|term| Value::push(&ring, term, g, cartan))
+                .collect();
 
-            if term.es.len() == 2 {
-                if term.es[1] > term.es[0] {
-                    term.es[1] = term.es[0];
-                    term.es[0] = e;
-                    term.n = -term.n;
+            return self;
+        }
+
+        return self.terms
+            .into_iter()
+            .map(|term| Value::braket_term(&ring, term, g, cartan))
+            .fold(Value::zero(&ring), |sum, value| sum.add(value));
+    }
+
+    /// `[g, self]`, the bracket with a generator on the left.
+    pub fn braket_gen_left(self, g: Gen, cartan: &CartanMatrix) -> Value<R> {
+        return self.braket_gen(g, cartan).times(-1);
+    }
+
+    /// `[self, other]` for any two elements. The right hand side is peeled
+    /// one generator at a time with `[x, [w, g]] = [[x, w], g] - [[x, g], w]`.
+    pub fn braket(self, other: &Value<R>, cartan: &CartanMatrix) -> Value<R> {
+        let mut sum = Value::zero(&self.ring);
+
+        for term in &other.terms {
+            let n = term.n.clone();
+            sum = sum.add(self.clone().braket_word(&term.gens().collect::<Vec<Gen>>(), cartan).scale(&n));
+        }
+
+        return sum;
+    }
+
+    fn braket_word(self, word: &[Gen], cartan: &CartanMatrix) -> Value<R> {
+        let (g, rest) = word.split_last().expect("Terms are never empty!");
+
+        if rest.is_empty() {
+            return self.braket_gen(*g, cartan);
+        }
+
+        let a = self.clone().braket_word(rest, cartan).braket_gen(*g, cartan);
+        let b = self.braket_gen(*g, cartan).braket_word(rest, cartan);
+
+        return a.add(b.times(-1));
+    }
+
+    fn braket_term(ring: &R, mut term: Term<R>, g: Gen, cartan: &CartanMatrix) -> Value<R> {
+        let last = term.last();
+
+        match (last, g) {
+            // [H(j), E(i)] = C(j, i) E(i), [H(j), F(i)] = -C(j, i) F(i)
+            (Gen::H(j), Gen::E(i)) => return Value::single(ring, ring.mul(&term.n, &ring.lift_i32(cartan.at(j, i))), vec![g]),
+            (Gen::H(j), Gen::F(i)) => return Value::single(ring, ring.mul(&term.n, &ring.lift_i32(-cartan.at(j, i))), vec![g]),
+            (Gen::H(_), Gen::H(_)) => return Value::zero(ring),
+
+            // [u, H(i)] = -[H(i), u], and H(i) acts on u by its weight.
+            (_, Gen::H(i)) => {
+                let weight = term.es.iter().map(|e| cartan.at(i, *e)).sum::<i32>();
+                let weight = if let Gen::E(_) = last { -weight } else { weight };
+
+                term.n = ring.mul(&term.n, &ring.lift_i32(weight));
+                return Value::zero(ring).add(Value { ring: ring.clone(), terms: vec![term] });
+            }
+
+            // Same kind: grow the left-normed bracket.
+            (last, g) if last.kind() == g.kind() => {
+                return Value { ring: ring.clone(), terms: Value::push(ring, term, g, cartan).into_iter().collect() };
+            }
+
+            // Opposite kinds: [E(k), F(i)] = δ H(i) and [F(k), E(i)] = -δ H(i),
+            // longer words go through [[u, x], g] = [[u, g], x] + [u, [x, g]].
+            (last, g) => {
+                let sign = if let Gen::E(_) = last { 1 } else { -1 };
+                let delta = last.index() == g.index();
+
+                if term.es.len() == 1 {
+                    if !delta {
+                        return Value::zero(ring);
+                    }
+
+                    return Value::single(ring, ring.mul(&term.n, &ring.lift_i32(sign)), vec![Gen::H(g.index())]);
                 }
+
+                term.es.pop();
+
+                let u = Value { ring: ring.clone(), terms: vec![term] };
+                let mut out = u.clone().braket_gen(g, cartan).braket_gen(last, cartan);
+
+                if delta {
+                    out = out.add(u.braket_gen(Gen::H(g.index()), cartan).times(sign));
+                }
+
+                return out;
             }
         }
+    }
 
-        return self;
+    fn push(ring: &R, mut term: Term<R>, g: Gen, cartan: &CartanMatrix) -> Option<Term<R>> {
+        term.es.push(g.index());
+
+        if term.es.len() == 2 && term.es[1] >= term.es[0] {
+            if term.es[1] == term.es[0] {
+                return None;
+            }
+
+            term.es.swap(0, 1);
+            term.n = ring.neg(&term.n);
+        }
+
+        // Terms that have just become a Serre relation vanish in the algebra.
+        if cartan.is_serre_word(&term.es) {
+            return None;
+        }
+
+        return Some(term);
     }
 
-    fn times(mut self, n: i32) -> Value {
+    fn single(ring: &R, n: R::Elem, es: Vec<Gen>) -> Value<R> {
+        return Value::zero(ring).add(Value { ring: ring.clone(), terms: vec![Term::new(n, es)] });
+    }
+
+    pub fn times(self, n: i32) -> Value<R> {
+        let n = self.ring.lift_i32(n);
+        return self.scale(&n);
+    }
+
+    pub fn scale(mut self, n: &R::Elem) -> Value<R> {
         for term in &mut self.terms {
-            term.n *= n; 
+            term.n = self.ring.mul(&term.n, n);
         }
 
+        self.terms.retain(|term| !self.ring.is_zero(&term.n));
+
         return self;
     }
 
-    fn add(mut self, b: Value) -> Value {
+    /// Whether there are no terms left, which is zero but misses terms that
+    /// only cancel through the Jacobi identity or the Serre relations.
+    pub fn equals_zero(&self) -> bool {
+        return self.terms.is_empty();
+    }
+
+    /// Whether the value is zero in the algebra. Each degree is expanded into
+    /// the free associative algebra and reduced against the ideal the Serre
+    /// relations generate there, so this is exact, but it takes time and
+    /// memory exponential in the length of the terms.
+    pub fn is_zero(&self, ideal: &Ideal<R>) -> bool {
+        let mut degrees: HashMap<(Kind, Vec<usize>), Poly<i32, R>> = HashMap::new();
+
+        for term in &self.terms {
+            // Like terms are merged, and the H(i) are linearly independent.
+            if term.kind == Kind::H {
+                return false;
+            }
+
+            let mut degree = vec![0; ideal.cartan.rank()];
+            term.es.iter().for_each(|e| degree[*e as usize - 1] += 1);
+
+            let poly = degrees.entry((term.kind, degree)).or_default();
+
+            for (word, n) in left_normed(&term.es, &self.ring) {
+                lyndon::add_term(poly, word, self.ring.mul(&term.n, &n), &self.ring);
+            }
+        }
+
+        // The F's satisfy the same Serre relations as the E's.
+        return degrees.into_iter().all(|((_, degree), poly)| ideal.reduce(&degree, poly).is_empty());
+    }
+}
+
+/// The expansion of `[..[w0, w1].., wk]` into the free associative algebra.
+fn left_normed<R: Ring>(word: &[i32], ring: &R) -> Poly<i32, R> {
+    let (first, rest) = word.split_first().expect("Terms are never empty!");
+
+    return rest.iter().fold(BTreeMap::from([(vec![*first], ring.lift_i32(1))]), |poly, e| braket_letter(&poly, *e, ring));
+}
+
+/// `[poly, e] = poly e - e poly`.
+fn braket_letter<R: Ring>(poly: &Poly<i32, R>, e: i32, ring: &R) -> Poly<i32, R> {
+    let mut out = BTreeMap::new();
+
+    for (word, n) in poly {
+        lyndon::add_term(&mut out, [word.as_slice(), &[e]].concat(), n.clone(), ring);
+        lyndon::add_term(&mut out, [&[e], word.as_slice()].concat(), ring.neg(n), ring);
+    }
+
+    return out;
+}
+
+type Rows<R> = BTreeMap<Vec<i32>, Poly<i32, R>>;
+
+/// The ideal of the free Lie algebra generated by the Serre relations,
+/// expanded into the free associative algebra. Each degree, counted per
+/// generator, is kept as rows in echelon form keyed by their smallest word.
+///
+/// Degrees are built as they are first needed and then kept, so one ideal
+/// is worth sharing between every value of a search.
+pub struct Ideal<R: Ring> {
+    cartan: CartanMatrix,
+    ring: R,
+    degrees: RwLock<HashMap<Vec<usize>, Rows<R>>>,
+}
+
+impl<R: Ring> Ideal<R> {
+    pub fn new(cartan: &CartanMatrix, ring: &R) -> Ideal<R> {
+        return Ideal { cartan: cartan.clone(), ring: ring.clone(), degrees: RwLock::new(HashMap::new()) };
+    }
+
+    /// What is left of `poly` after taking out as much of the ideal as it can,
+    /// empty exactly when `poly` is in the ideal.
+    fn reduce(&self, degree: &[usize], poly: Poly<i32, R>) -> Poly<i32, R> {
+        if let Some(rows) = self.degrees.read().expect("Ideal lock poisoned!").get(degree) {
+            return reduce(rows, poly, &self.ring);
+        }
+
+        let mut degrees = self.degrees.write().expect("Ideal lock poisoned!");
+        self.build(&mut degrees, degree);
+
+        return reduce(&degrees[degree], poly, &self.ring);
+    }
+
+    /// Builds a degree from the degrees one letter shorter, as the ideal is
+    /// spanned by the Serre relations and their brackets with generators on
+    /// the right.
+    fn build(&self, degrees: &mut HashMap<Vec<usize>, Rows<R>>, degree: &[usize]) {
+        if degrees.contains_key(degree) {
+            return;
+        }
+
+        let mut rows = BTreeMap::new();
+
+        for (k, count) in degree.iter().enumerate().filter(|(_, count)| **count > 0) {
+            let mut lower = degree.to_vec();
+            lower[k] = count - 1;
+            self.build(degrees, &lower);
+
+            for row in degrees[&lower].values() {
+                insert(&mut rows, braket_letter(row, k as i32 + 1, &self.ring), &self.ring);
+            }
+        }
+
+        // ad(E(i))^(1 - C(i, j)) E(j)
+        let rank = degree.len() as i32;
+
+        for (i, j) in (1..=rank).flat_map(|i| (1..=rank).map(move |j| (i, j))).filter(|(i, j)| i != j) {
+            let k = (1 - self.cartan.at(i, j)) as usize;
+            let count = |l: i32| if l == i { k } else { (l == j) as usize };
+
+            if (1..=rank).all(|l| degree[l as usize - 1] == count(l)) {
+                let word = [vec![j], vec![i; k]].concat();
+                insert(&mut rows, left_normed(&word, &self.ring), &self.ring);
+            }
+        }
+
+        degrees.insert(degree.to_vec(), rows);
+    }
+}
+
+/// Cancels the smallest word of `poly` against the row it leads, until it
+/// leads none. Rows are scaled rather than divided, so this works over the
+/// integers as well as over a field.
+fn reduce<R: Ring>(rows: &Rows<R>, mut poly: Poly<i32, R>, ring: &R) -> Poly<i32, R> {
+    while let Some((row, n)) = poly.iter().next().and_then(|(word, n)| Some((rows.get(word)?, n.clone()))) {
+        let lead = row.values().next().expect("Rows are never empty!").clone();
+
+        if lead != ring.lift_i32(1) {
+            poly.values_mut().for_each(|m| *m = ring.mul(m, &lead));
+        }
+
+        for (word, m) in row {
+            lyndon::add_term(&mut poly, word.clone(), ring.neg(&ring.mul(&n, m)), ring);
+        }
+
+        if lead != ring.lift_i32(1) {
+            ring.primitive(poly.values_mut());
+        }
+    }
+
+    return poly;
+}
+
+fn insert<R: Ring>(rows: &mut Rows<R>, poly: Poly<i32, R>, ring: &R) {
+    let mut poly = reduce(rows, poly, ring);
+
+    let Some((word, lead)) = poly.iter().next().map(|(word, n)| (word.clone(), n.clone())) else {
+        return;
+    };
+
+    if let Some(inverse) = ring.inverse(&lead) {
+        poly.values_mut().for_each(|m| *m = ring.mul(m, &inverse));
+    } else {
+        ring.primitive(poly.values_mut());
+    }
+
+    rows.insert(word, poly);
+}
+
+impl<R: Ring> Value<R> {
+    /// An exact one line form, `n:K:i,j,..` per term, read back by `read`.
+    pub fn write(&self) -> String {
+        if self.terms.is_empty() {
+            return "0".to_string();
+        }
+
+        return self.terms
+            .iter()
+            .map(|term| {
+                let es = term.es.iter().map(|i| i.to_string()).collect::<Vec<String>>();
+                format!("{}:{:?}:{}", term.n, term.kind, es.join(","))
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+    }
+
+    /// Reads a value written by `write`, with every generator index in
+    /// `1..=rank`.
+    pub fn read(src: &str, rank: usize, ring: &R) -> Option<Value<R>> {
+        if src.trim() == "0" {
+            return Some(Value::zero(ring));
+        }
+
+        let mut terms = vec![];
+
+        for token in src.split_whitespace() {
+            let mut parts = token.split(':');
+
+            let n = ring.parse(parts.next()?)?;
+            let kind = match parts.next()? {
+                "E" => Kind::E,
+                "F" => Kind::F,
+                "H" => Kind::H,
+                _ => return None,
+            };
+            let es = parts.next()?
+                .split(',')
+                .map(|i| i.parse::<i32>().ok().filter(|i| (1..=rank as i32).contains(i)).map(|i| kind.gen(i)))
+                .collect::<Option<Vec<Gen>>>()?;
+
+            if parts.next().is_some() || (kind == Kind::H && es.len() != 1) {
+                return None;
+            }
+
+            terms.push(Term::new(n, es));
+        }
+
+        return Some(Value { ring: ring.clone(), terms });
+    }
+}
+
+impl<R: Ring> Add for Value<R> {
+    type Output = Value<R>;
+
+    fn add(mut self, b: Value<R>) -> Value<R> {
         b.terms
             .into_iter()
             .for_each(|term_b| {
                 for term_a in &mut self.terms {
-                    if term_a.es == term_b.es {
-                        term_a.n += term_b.n;
+                    if term_a.es == term_b.es && term_a.kind == term_b.kind {
+                        term_a.n = self.ring.add(&term_a.n, &term_b.n);
                         return;
                     }
                 }
@@ -63,20 +553,16 @@ impl Value {
             });
 
         for i in (0..self.terms.len()).rev() {
-            if self.terms[i].n == 0 {
+            if self.ring.is_zero(&self.terms[i].n) {
                 self.terms.remove(i);
             }
         }
 
         return self;
     }
-
-    fn equals_zero(&self) -> bool {
-        return self.terms.len() == 0;
-    }
 }
 
-impl std::fmt::Display for Value {
+impl<R: Ring> std::fmt::Display for Value<R> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for term in &self.terms {
             write!(f, "{} * ", term.n)?;
@@ -85,11 +571,11 @@ impl std::fmt::Display for Value {
                 write!(f, "[")?;
             }
 
-            for i in 0..term.es.len() {
+            for (i, g) in term.gens().enumerate() {
                 if i == 0 {
-                    write!(f, "E({})", term.es[i])?;
+                    write!(f, "{g}")?;
                 } else {
-                    write!(f, ", E({})]", term.es[i])?;
+                    write!(f, ", {g}]")?;
                 }
             }
 
@@ -101,78 +587,91 @@ impl std::fmt::Display for Value {
     }
 }
 
-///
-const C: [[i32; 3]; 3] = [
-    [  2, -1, -1 ],
-    [ -1,  2, -2 ],
-    [ -1, -1,  2 ]
-];
+///////
 
-fn c(x: i32, y: i32) -> i32 {
-    return C[x as usize][y as usize];
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ring::{Integers, Modular};
 
+    fn gen(g: Gen) -> Value<Integers> {
+        return Value::gen(&Integers, g);
+    }
 
-///
-pub fn find_counter_example() {
-    // nx = [E(1), E(2)]
-    let mut nx = Value::new(vec![Term::new(-1, vec![ 2, 1 ])]);
-
-    // nx_f1 = [[E(1), E(2)], F(1)] =
-    let mut nx_f1 = Value::new(vec![Term::new(-1, vec![ 2 ])]);
-    // nx_f2 = [[E(1), E(2)], F(1)]
-    let mut nx_f2 = Value::new(vec![Term::new(0, vec![ 1 ])]);
-    // nx_f3 = [[E(1), E(2)], F(1)]
-    let mut nx_f3 = Value::new(vec![]);
-
-    // nx_h1 = [[E(1), E(2)], H(1)]
-    let mut nx_h1 = Value::new(vec![Term::new(1, vec![ 2, 1 ])]);
-    // nx_h2 = [[E(1), E(2)], H(1)]
-    let mut nx_h2 = Value::new(vec![Term::new(1, vec![ 2, 1 ])]);
-    // nx_h3 = [[E(1), E(2)], H(2)]
-    let mut nx_h3 = Value::new(vec![Term::new(-2, vec![ 2, 1 ])]);
-
-    for n in 2.. {
-        if n % 500 == 0 {
-            println!("Checking {}", n);
-        }
-        // println!("n{n} = {nx}");
-        // println!("n{n}_h1 = {nx_h1}");
-
-        nx_f1 = if n % 3 + 1 == 1 {
-            // [nx_f1, E(n % 3 + 1)] - nx_h1
-            nx_f1.braket(n % 3 + 1).add(nx_h1.clone().times(-1))
-        } else {
-            // [nx_f1, E(n % 3 + 1)]
-            nx_f1.braket(n % 3 + 1)
-        };
-
-        nx_f2 = if n % 3 + 1 == 2 {
-            // [nx_f1, E(n % 3 + 1)] - nx_h1
-            nx_f2.braket(n % 3 + 1).add(nx_h2.clone().times(-1))
-        } else {
-            // [nx_f1, E(n % 3 + 1)]
-            nx_f2.braket(n % 3 + 1)
-        };
-
-        nx_f3 = if n % 3 + 1 == 1 {
-            // [nx_f1, E(n % 3 + 1)] - nx_h1
-            nx_f3.braket(n % 3 + 1).add(nx_h3.clone().times(-1))
-        } else {
-            // [nx_f1, E(n % 3 + 1)]
-            nx_f3.braket(n % 3 + 1)
-        };
-
-        nx_h1 = nx_h1.braket(n % 3 + 1).add(nx.clone().braket(n % 3 + 1).times(-c(0, n % 3)));
-        nx_h2 = nx_h2.braket(n % 3 + 1).add(nx.clone().braket(n % 3 + 1).times(-c(1, n % 3)));
-        nx_h3 = nx_h3.braket(n % 3 + 1).add(nx.clone().braket(n % 3 + 1).times(-c(2, n % 3)));
-
-        // nx = [nx, E(n % 3 + 1)]
-        nx = nx.braket(n % 3 + 1);
-
-        if nx_f1.equals_zero() && nx_f2.equals_zero() && nx_f3.equals_zero() {
-            println!("Found zero @ {n}!!");
-            return;
-        }
+    fn brak(a: Value<Integers>, b: Value<Integers>, cartan: &CartanMatrix) -> Value<Integers> {
+        return a.braket(&b, cartan);
+    }
+
+    fn same(a: Value<Integers>, b: Value<Integers>) -> bool {
+        return a.add(b.times(-1)).equals_zero();
+    }
+
+    #[test]
+    fn brackets_with_f_and_h_in_a2() {
+        let a2 = CartanMatrix::dynkin('A', 2).unwrap();
+        let e12 = brak(gen(Gen::E(1)), gen(Gen::E(2)), &a2);
+        let f12 = brak(gen(Gen::F(1)), gen(Gen::F(2)), &a2);
+
+        assert!(same(brak(gen(Gen::E(1)), gen(Gen::F(1)), &a2), gen(Gen::H(1))));
+        assert!(brak(gen(Gen::E(1)), gen(Gen::F(2)), &a2).equals_zero());
+        assert!(same(brak(gen(Gen::H(1)), gen(Gen::E(2)), &a2), gen(Gen::E(2)).times(-1)));
+        assert!(same(brak(gen(Gen::H(1)), gen(Gen::F(2)), &a2), gen(Gen::F(2))));
+        assert!(same(brak(gen(Gen::H(2)), e12.clone(), &a2), e12.clone()));
+
+        // [[E1, E2], F2] = [E1, H2] = E1 and [[E1, E2], F1] = [H1, E2] = -E2
+        assert!(same(brak(e12.clone(), gen(Gen::F(2)), &a2), gen(Gen::E(1))));
+        assert!(same(brak(e12.clone(), gen(Gen::F(1)), &a2), gen(Gen::E(2)).times(-1)));
+
+        // [[F1, F2], E1] = [-H1, F2] = -F2, and [F1, [F1, F2]] is a Serre relation.
+        assert!(same(brak(f12.clone(), gen(Gen::E(1)), &a2), gen(Gen::F(2)).times(-1)));
+        assert!(brak(gen(Gen::F(1)), f12.clone(), &a2).equals_zero());
+
+        // [[E1, E2], [F1, F2]] = [[[E1, E2], F1], F2] - [[[E1, E2], F2], F1]
+        //                      = [-E2, F2] - [E1, F1] = -H2 - H1
+        assert!(same(brak(e12, f12, &a2), gen(Gen::H(1)).add(gen(Gen::H(2))).times(-1)));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn brackets_with_f_in_a3() {
+        let a3 = CartanMatrix::dynkin('A', 3).unwrap();
+        let e123 = brak(brak(gen(Gen::E(1)), gen(Gen::E(2)), &a3), gen(Gen::E(3)), &a3);
+
+        // [[[E1, E2], E3], F3] = [[E1, E2], H3] = [E1, E2]
+        let e12 = brak(gen(Gen::E(1)), gen(Gen::E(2)), &a3);
+        assert!(same(brak(e123.clone(), gen(Gen::F(3)), &a3), e12));
+
+        // [[[E1, E2], E3], F2] = [E1, E3] = 0
+        assert!(brak(e123.clone(), gen(Gen::F(2)), &a3).equals_zero());
+
+        // [[[E1, E2], E3], F1] = [[H1, E2], E3] = -[E2, E3]
+        let e23 = brak(gen(Gen::E(2)), gen(Gen::E(3)), &a3);
+        assert!(same(brak(e123, gen(Gen::F(1)), &a3), e23.times(-1)));
+    }
+
+    #[test]
+    fn normal_form_sees_jacobi_and_serre_cancellations() {
+        let cartan = CartanMatrix::new(vec![vec![2, -1, -1], vec![-1, 2, -2], vec![-1, -1, 2]]).unwrap();
+        let ideal = Ideal::new(&cartan, &Integers);
+        let e = |i| gen(Gen::E(i));
+
+        let jacobi = brak(brak(e(1), e(2), &cartan), e(3), &cartan)
+            .add(brak(brak(e(2), e(3), &cartan), e(1), &cartan))
+            .add(brak(brak(e(3), e(1), &cartan), e(2), &cartan));
+        assert!(!jacobi.equals_zero() && jacobi.is_zero(&ideal));
+
+        // [[E1, E2], E3, E3, E3] is ad(E3)^3 of [E1, E2], which lies in the
+        // ideal of ad(E3)^2 E1 and ad(E3)^3 E2 without being either.
+        let serre = [2, 3, 3, 3].iter().fold(e(1), |n, i| brak(n, e(*i), &cartan));
+        assert!(!serre.equals_zero() && serre.is_zero(&ideal));
+
+        assert!(!brak(brak(e(1), e(2), &cartan), e(3), &cartan).is_zero(&ideal));
+        assert!(!gen(Gen::H(1)).is_zero(&ideal));
+
+        let ring = Modular::new(7).unwrap();
+        let f = |i| Value::gen(&ring, Gen::F(i));
+        let jacobi = f(1).braket(&f(2), &cartan).braket(&f(3), &cartan)
+            .add(f(2).braket(&f(3), &cartan).braket(&f(1), &cartan))
+            .add(f(3).braket(&f(1), &cartan).braket(&f(2), &cartan));
+        assert!(jacobi.is_zero(&Ideal::new(&cartan, &ring)));
+    }
+}
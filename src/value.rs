@@ -1,8 +1,10 @@
 use std::fmt::Display;
 
+use crate::integer::Integer;
+
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub enum Value {
-    Number(i32),
+    Number(Integer),
     Braket(Box<Value>, Box<Value>),
     Kind(String, i32),
     Negative(Box<Value>),
@@ -12,16 +14,16 @@ pub enum Value {
 
 impl Value {
     pub fn zero() -> Value {
-        return Value::Number(0)
+        return Value::Number(Integer::zero())
     }
 
     pub fn one() -> Value {
-        return Value::Number(1)
+        return Value::Number(Integer::one())
     }
 
-    pub fn into_number(&self) -> Option<i32> {
+    pub fn into_number(&self) -> Option<Integer> {
         match self {
-            Value::Number(num) => Some(*num),
+            Value::Number(num) => Some(num.clone()),
             Value::Negative(num) => Some(-num.into_number()?),
             _ => None,
         }
@@ -61,10 +63,10 @@ pub fn f(n: i32) -> Value {
     return Value::Kind("F".to_string(), n);
 }
 
-pub fn number(n: i32) -> Value {
-    if n >= 0 {
-        Value::Number(n)
-    } else {
+pub fn number(n: Integer) -> Value {
+    if n.is_negative() {
         Value::Negative(Box::new(Value::Number(-n)))
+    } else {
+        Value::Number(n)
     }
 }
\ No newline at end of file